@@ -0,0 +1,18 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D scene;
+uniform float threshold;
+uniform float knee;
+
+// keeps what exceeds `threshold`, easing in over `knee` below it
+void main() {
+    vec3 color = texture(scene, TexCoord).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    float soft = clamp(brightness - threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-4);
+    float contribution = max(soft, brightness - threshold) / max(brightness, 1e-4);
+    FragColor = vec4(color * contribution, 1.0);
+}
@@ -0,0 +1,13 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D scene;
+uniform sampler2D bloom;
+uniform float strength;
+
+void main() {
+    vec3 color = texture(scene, TexCoord).rgb + strength * texture(bloom, TexCoord).rgb;
+    FragColor = vec4(color, 1.0);
+}
@@ -0,0 +1,16 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D image;
+uniform sampler3D lut;
+uniform float amount;
+
+void main() {
+    vec3 color = texture(image, TexCoord).rgb;
+    // the centers of the outermost texels map to 0 and 1
+    float size = float(textureSize(lut, 0).x);
+    vec3 coord = color * (size - 1.0) / size + 0.5 / size;
+    FragColor = vec4(mix(color, texture(lut, coord).rgb, amount), 1.0);
+}
@@ -0,0 +1,30 @@
+#version 330 core
+out vec4 FragColor;
+
+#include "include/shading.glsl"
+
+uniform sampler2D gPosition;
+uniform sampler2D gAlbedoSpec;
+uniform sampler2D gEmissive;
+uniform sampler2D gDepth;
+uniform vec3 viewPos;
+uniform vec3 fogColor;
+uniform vec3 ambientColor;
+
+void main() {
+    ivec2 texel = ivec2(gl_FragCoord.xy);
+    vec4 position = texelFetch(gPosition, texel, 0);
+
+    // restore the scene depth for the forward passes that follow
+    gl_FragDepth = texelFetch(gDepth, texel, 0).r;
+
+    if (position.w == 0.0) {
+        FragColor = vec4(fogColor, 1.0);
+        return;
+    }
+
+    vec3 albedo = texelFetch(gAlbedoSpec, texel, 0).rgb;
+    vec3 emissive = texelFetch(gEmissive, texel, 0).rgb;
+    float fog = fogFactor(length(position.xyz - viewPos));
+    FragColor = vec4((1.0 - fog) * fogColor + fog * (ambientColor * albedo + emissive), 1.0);
+}
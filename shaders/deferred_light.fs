@@ -0,0 +1,42 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec3 LightPos;
+in vec3 LightColor;
+
+#include "include/shading.glsl"
+
+uniform sampler2D gPosition;
+uniform sampler2D gNormal;
+uniform sampler2D gAlbedoSpec;
+uniform sampler2D gEmissive;
+uniform vec3 viewPos;
+
+// one light's share of lighting.fs; contributions add up through blending
+void main() {
+    ivec2 texel = ivec2(gl_FragCoord.xy);
+    vec4 position = texelFetch(gPosition, texel, 0);
+
+    if (position.w == 0.0) {
+        discard;
+    }
+
+    // the alpha channels hold the shading model and its factors, see gbuffer.fs
+    bool pbr = texelFetch(gEmissive, texel, 0).a > 0.5;
+    vec3 fragPos = position.xyz;
+    vec3 ray = LightPos - fragPos;
+    float lightStrength = lightFalloff(length(ray), pbr);
+
+    if (!lightReaches(lightStrength, pbr)) {
+        discard;
+    }
+
+    vec4 normal = texelFetch(gNormal, texel, 0);
+    vec4 albedo = texelFetch(gAlbedoSpec, texel, 0);
+    Surface surface = Surface(pbr, albedo.rgb, albedo.a, normal.w, albedo.a, normal.w);
+    vec3 viewDir = normalize(viewPos - fragPos);
+    vec3 lit = shade(surface, normal.xyz, normalize(ray), viewDir, LightColor);
+
+    float fog = fogFactor(length(fragPos - viewPos));
+    FragColor = vec4(fog * lit * lightStrength, 1.0);
+}
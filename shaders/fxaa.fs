@@ -0,0 +1,40 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D image;
+
+const float REDUCE_MIN = 1.0 / 128.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+// longest blur along an edge, in texels
+const float SPAN_MAX = 8.0;
+const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+
+// Lottes' FXAA in its compact form: the luma gradient of the four diagonal
+// neighbours gives the edge direction, along which two and four taps are averaged
+void main() {
+    vec2 texel = 1.0 / vec2(textureSize(image, 0));
+    vec3 rgbM = texture(image, TexCoord).rgb;
+    float lumaNW = dot(texture(image, TexCoord + vec2(-1.0, -1.0) * texel).rgb, LUMA);
+    float lumaNE = dot(texture(image, TexCoord + vec2(1.0, -1.0) * texel).rgb, LUMA);
+    float lumaSW = dot(texture(image, TexCoord + vec2(-1.0, 1.0) * texel).rgb, LUMA);
+    float lumaSE = dot(texture(image, TexCoord + vec2(1.0, 1.0) * texel).rgb, LUMA);
+    float lumaM = dot(rgbM, LUMA);
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    vec2 dir = vec2(-((lumaNW + lumaNE) - (lumaSW + lumaSE)), (lumaNW + lumaSW) - (lumaNE + lumaSE));
+    float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * rcpDirMin, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * texel;
+
+    vec3 rgbA = 0.5 * (texture(image, TexCoord + dir * (1.0 / 3.0 - 0.5)).rgb
+                       + texture(image, TexCoord + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 rgbB = 0.5 * rgbA + 0.25 * (texture(image, TexCoord - dir * 0.5).rgb
+                                     + texture(image, TexCoord + dir * 0.5).rgb);
+    float lumaB = dot(rgbB, LUMA);
+
+    // the wide average crossed another edge, keep the narrow one
+    FragColor = vec4(lumaB < lumaMin || lumaB > lumaMax ? rgbA : rgbB, 1.0);
+}
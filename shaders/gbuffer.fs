@@ -0,0 +1,36 @@
+#version 330 core
+layout (location = 0) out vec4 gPosition;
+layout (location = 1) out vec4 gNormal;
+layout (location = 2) out vec4 gAlbedoSpec;
+layout (location = 3) out vec4 gEmissive;
+
+in vec3 Normal;
+in vec3 FragPos;
+in vec2 TexCoord;
+flat in vec3 Albedo;
+flat in float Specular;
+flat in float Shininess;
+flat in float Metallic;
+flat in float Roughness;
+flat in vec3 Emissive;
+flat in int Pbr;
+
+uniform sampler2D diffuseMap;
+uniform sampler2D specularMap;
+
+void main() {
+    vec3 albedo = Albedo * texture(diffuseMap, TexCoord).rgb;
+    vec4 specularMapTexel = texture(specularMap, TexCoord);
+
+    // w marks covered pixels, the background stays at 0
+    gPosition = vec4(FragPos, 1.0);
+    // the alphas hold the two factors the shading model reads, and which it is
+    if (Pbr != 0) {
+        gNormal = vec4(normalize(Normal), Roughness * specularMapTexel.g);
+        gAlbedoSpec = vec4(albedo, Metallic * specularMapTexel.b);
+    } else {
+        gNormal = vec4(normalize(Normal), Shininess);
+        gAlbedoSpec = vec4(albedo, Specular * specularMapTexel.r);
+    }
+    gEmissive = vec4(Emissive, float(Pbr));
+}
@@ -0,0 +1,11 @@
+// The storage block of particles.rs, shared by particles.cs and particles.vs.
+// PARTICLE_BINDING is injected by particles.rs.
+
+struct Particle {
+    vec4 position; // w: age in seconds, negative until the first spawn
+    vec4 velocity; // w: lifetime in seconds
+};
+
+layout (std430, binding = PARTICLE_BINDING) buffer Particles {
+    Particle particles[];
+};
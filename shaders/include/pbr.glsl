@@ -0,0 +1,54 @@
+// Metallic-roughness BRDF, the PBR shading model of shading.glsl: Lambert
+// diffuse and Cook-Torrance specular with a GGX distribution, Smith-Schlick
+// geometry and Schlick Fresnel.
+
+const float PI = 3.14159265;
+
+// share of microfacets facing `halfDir`
+float distributionGGX(float nDotH, float roughness) {
+    float a = roughness * roughness;
+    float a2 = a * a;
+    float d = nDotH * nDotH * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+// share of microfacets neither shadowed nor masked, for punctual lights
+float geometrySmith(float nDotV, float nDotL, float roughness) {
+    float r = roughness + 1.0;
+    float k = r * r / 8.0;
+    float v = nDotV / (nDotV * (1.0 - k) + k);
+    float l = nDotL / (nDotL * (1.0 - k) + k);
+    return v * l;
+}
+
+vec3 fresnelSchlick(float cosTheta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(1.0 - cosTheta, 5.0);
+}
+
+// radiance reflected towards `viewDir` of `radiance` arriving from `lightDir`
+vec3 cookTorrance(vec3 norm, vec3 lightDir, vec3 viewDir, vec3 radiance, vec3 albedo,
+                  float metallic, float roughness) {
+    float nDotL = max(dot(norm, lightDir), 0.0);
+
+    if (nDotL == 0.0) {
+        return vec3(0.0);
+    }
+
+    // perfectly smooth surfaces would reflect point lights as infinitely small dots
+    roughness = max(roughness, 0.04);
+
+    vec3 halfDir = normalize(lightDir + viewDir);
+    float nDotV = max(dot(norm, viewDir), 1e-4);
+    float nDotH = max(dot(norm, halfDir), 0.0);
+
+    // dielectrics reflect about 4% head-on, metals their albedo
+    vec3 f0 = mix(vec3(0.04), albedo, metallic);
+    vec3 fresnel = fresnelSchlick(max(dot(halfDir, viewDir), 0.0), f0);
+    vec3 specular = distributionGGX(nDotH, roughness) * geometrySmith(nDotV, nDotL, roughness)
+        * fresnel / (4.0 * nDotV * nDotL);
+
+    // what is not reflected is refracted and diffused, except by metals
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - metallic) * albedo / PI;
+
+    return (diffuse + specular) * radiance * nDotL;
+}
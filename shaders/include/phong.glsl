@@ -0,0 +1,10 @@
+// Phong reflection, the classic shading model of shading.glsl.
+
+// diffuse plus specular of a light arriving from `lightDir`
+vec3 phong(vec3 norm, vec3 lightDir, vec3 viewDir, vec3 lightColor, float specularStrength,
+           float shininess) {
+    float diff = max(dot(norm, lightDir), 0.0);
+    vec3 reflectDir = reflect(-lightDir, norm);
+    float spec = pow(max(dot(viewDir, reflectDir), 0.0), shininess);
+    return (diff + specularStrength * spec) * lightColor;
+}
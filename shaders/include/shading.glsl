@@ -0,0 +1,57 @@
+// The shading models shared by the forward and deferred paths, picked per
+// surface: classic Phong with exponential falloff, or metallic-roughness PBR with
+// windowed inverse-square falloff (see `Shading` in material.rs).
+// LIGHT_DECAY, LIGHT_CUTOFF, LIGHT_RANGE, LIGHT_INTENSITY and DST_DECAY are
+// injected from lights.rs.
+
+#include "phong.glsl"
+#include "pbr.glsl"
+
+// material of a surface point; classic shading reads specular and shininess,
+// PBR shading metallic and roughness
+struct Surface {
+    bool pbr;
+    vec3 albedo;
+    float specular;
+    float shininess;
+    float metallic;
+    float roughness;
+};
+
+// strength of a point light `distance` units away
+float lightFalloff(float distance, bool pbr) {
+    if (pbr) {
+        // fades to exactly zero at LIGHT_RANGE, where clusters stop listing the light
+        float window = clamp(1.0 - pow(distance / LIGHT_RANGE, 4.0), 0.0, 1.0);
+        return LIGHT_INTENSITY * window * window / (distance * distance + 1.0);
+    }
+
+    return pow(LIGHT_DECAY, -distance);
+}
+
+// whether a light of strength `lightFalloff(distance, pbr)` is worth shading
+bool lightReaches(float strength, bool pbr) {
+    return pbr ? strength > 0.0 : strength >= LIGHT_CUTOFF;
+}
+
+// strength of a directional light; PBR shading scales it so that a white light
+// gives a white matte surface facing it its albedo, as classic shading does
+float directionalStrength(bool pbr) {
+    return pbr ? PI : 1.0;
+}
+
+// share of a surface's color that is not fog, `distance` units from the eye
+float fogFactor(float distance) {
+    return pow(DST_DECAY, -distance);
+}
+
+// color of `surface` lit by `lightColor` arriving from `lightDir`, before falloff
+vec3 shade(Surface surface, vec3 norm, vec3 lightDir, vec3 viewDir, vec3 lightColor) {
+    if (surface.pbr) {
+        return cookTorrance(norm, lightDir, viewDir, lightColor, surface.albedo, surface.metallic,
+                            surface.roughness);
+    }
+
+    return phong(norm, lightDir, viewDir, lightColor, surface.specular, surface.shininess)
+        * surface.albedo;
+}
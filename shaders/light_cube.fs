@@ -1,8 +1,10 @@
 #version 330 core
 out vec4 FragColor;
 
-uniform vec3 lightColor;
+in vec3 LightColor;
+
+uniform float intensity;
 
 void main() {
-    FragColor = vec4(lightColor, 1.0);
+    FragColor = vec4(LightColor * intensity, 1.0);
 }
\ No newline at end of file
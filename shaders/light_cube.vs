@@ -1,10 +1,14 @@
 #version 330 core
 layout (location = 0) in vec3 aPos;
+layout (location = 2) in mat4 aModel;
+layout (location = 6) in vec3 aColor;
+
+out vec3 LightColor;
 
-uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
 
 void main() {
-    gl_Position = projection * view * model * vec4(aPos, 1.0);
+    LightColor = aColor;
+    gl_Position = projection * view * aModel * vec4(aPos, 1.0);
 }
@@ -3,50 +3,160 @@ out vec4 FragColor;
 
 in vec3 Normal;
 in vec3 FragPos;
+in vec2 TexCoord;
+in float ViewDepth;
+flat in vec3 Albedo;
+flat in float Specular;
+flat in float Shininess;
+flat in float Metallic;
+flat in float Roughness;
+flat in vec3 Emissive;
+flat in int Pbr;
 
-#define MAX_LIGHTS 225
-#define SPECULAR_STRENGTH 0.5
-#define LIGHT_DECAY 1.2
-#define DST_DECAY 1.075
+// MAX_POINT_SHADOWS and CASCADES are injected by renderer.rs
+#include "include/shading.glsl"
+
+// material maps, white where a material has none
+uniform sampler2D diffuseMap;
+uniform sampler2D specularMap;
+
+// positions and colors of every light, interleaved, see lights.rs
+uniform samplerBuffer lights;
+
+// clustered light lists, see cluster.rs
+uniform usamplerBuffer clusterTable;
+uniform usamplerBuffer clusterIndices;
+uniform uvec3 clusterDims;
+uniform vec2 clusterScreen;
+uniform float clusterNear;
+uniform float clusterScale;
+
+// shadow maps, see shadow.rs
+uniform samplerCube pointShadows[MAX_POINT_SHADOWS];
+uniform int shadowLights[MAX_POINT_SHADOWS];
+uniform float shadowFar;
+uniform bool sunEnabled;
+uniform vec3 sunDirection;
+uniform vec3 sunColor;
+uniform sampler2DArrayShadow sunShadow;
+uniform mat4 cascadeMatrices[CASCADES];
+uniform vec3 cascadeSplits;
+
+const vec3 pcfOffsets[20] = vec3[](
+    vec3(1, 1, 1), vec3(1, -1, 1), vec3(-1, -1, 1), vec3(-1, 1, 1),
+    vec3(1, 1, -1), vec3(1, -1, -1), vec3(-1, -1, -1), vec3(-1, 1, -1),
+    vec3(1, 1, 0), vec3(1, -1, 0), vec3(-1, -1, 0), vec3(-1, 1, 0),
+    vec3(1, 0, 1), vec3(-1, 0, 1), vec3(1, 0, -1), vec3(-1, 0, -1),
+    vec3(0, 1, 1), vec3(0, -1, 1), vec3(0, -1, -1), vec3(0, 1, -1));
 
-uniform vec3 lightPositions[MAX_LIGHTS];
-uniform vec3 lightColors[MAX_LIGHTS];
 uniform vec3 viewPos;
-uniform vec3 objectColor;
 uniform vec3 fogColor;
 uniform vec3 ambientColor;
-uniform uint lights;
+
+// sampler arrays only take constant indices in GLSL 3.30, one case per slot
+float pointShadowDistance(int slot, vec3 dir) {
+    switch (slot) {
+        case 0: return texture(pointShadows[0], dir).r;
+        case 1: return texture(pointShadows[1], dir).r;
+        case 2: return texture(pointShadows[2], dir).r;
+        default: return texture(pointShadows[3], dir).r;
+    }
+}
+
+// fraction of light reaching the fragment, `ray` pointing from it to the light
+float pointVisibility(uint light, vec3 ray) {
+    int slot = -1;
+
+    for (int s = 0; s < MAX_POINT_SHADOWS; ++s) {
+        if (shadowLights[s] == int(light)) {
+            slot = s;
+        }
+    }
+
+    if (slot < 0) {
+        return 1.0;
+    }
+
+    float current = length(ray);
+    float bias = 0.05;
+    float disk = (1.0 + ViewDepth / shadowFar) * 0.04;
+    float lit = 0.0;
+
+    for (int k = 0; k < 20; ++k) {
+        float closest = pointShadowDistance(slot, -ray + pcfOffsets[k] * disk) * shadowFar;
+        lit += current - bias > closest ? 0.0 : 1.0;
+    }
+
+    return lit / 20.0;
+}
+
+float sunVisibility(vec3 norm) {
+    int cascade = 0;
+
+    while (cascade < CASCADES && ViewDepth > cascadeSplits[cascade]) {
+        ++cascade;
+    }
+
+    if (cascade == CASCADES) {
+        return 1.0;
+    }
+
+    vec4 lightSpace = cascadeMatrices[cascade] * vec4(FragPos, 1.0);
+    vec3 coords = lightSpace.xyz / lightSpace.w * 0.5 + 0.5;
+    float bias = max(0.002 * (1.0 - dot(norm, -sunDirection)), 0.0005);
+    vec2 texel = 1.0 / vec2(textureSize(sunShadow, 0).xy);
+    float lit = 0.0;
+
+    for (int x = -1; x <= 1; ++x) {
+        for (int y = -1; y <= 1; ++y) {
+            vec2 uv = coords.xy + vec2(x, y) * texel;
+            lit += texture(sunShadow, vec4(uv, float(cascade), coords.z - bias));
+        }
+    }
+
+    return lit / 9.0;
+}
 
 void main() {
     vec3 norm = normalize(Normal);
-    vec3 diffuse_acc = vec3(0.);
-    vec3 specular_acc = vec3(0.);
+    vec3 viewDir = normalize(viewPos - FragPos);
+    vec4 specularMapTexel = texture(specularMap, TexCoord);
+    Surface surface = Surface(
+        Pbr != 0,
+        Albedo * texture(diffuseMap, TexCoord).rgb,
+        Specular * specularMapTexel.r,
+        Shininess,
+        Metallic * specularMapTexel.b,
+        Roughness * specularMapTexel.g);
+    vec3 light = vec3(0.);
+
+    uvec3 cluster = uvec3(
+        clamp(gl_FragCoord.xy / clusterScreen, 0.0, 0.9999) * vec2(clusterDims.xy),
+        clamp(log(ViewDepth / clusterNear) * clusterScale, 0.0, float(clusterDims.z) - 1.0));
+    uint clusterIndex = cluster.x + clusterDims.x * (cluster.y + clusterDims.y * cluster.z);
+    uvec2 range = texelFetch(clusterTable, int(clusterIndex)).rg;
 
-    for (uint i = uint(0); i < lights; ++i) {
-        vec3 ray = lightPositions[i] - FragPos;
-        float lightStrength = pow(LIGHT_DECAY, -length(ray));
+    for (uint k = uint(0); k < range.y; ++k) {
+        uint i = texelFetch(clusterIndices, int(range.x + k)).r;
+        vec3 ray = texelFetch(lights, 2 * int(i)).xyz - FragPos;
+        float lightStrength = lightFalloff(length(ray), surface.pbr);
 
-        if (lightStrength < 0.01) {
+        if (!lightReaches(lightStrength, surface.pbr)) {
             continue;
         }
 
-        vec3 lightDir = normalize(ray);
-        float diff = max(dot(norm, lightDir), 0.0);
-        vec3 diffuse = diff * lightColors[i] * lightStrength;
-
-        vec3 viewDir = normalize(viewPos - FragPos);
-        vec3 reflectDir = reflect(-lightDir, norm);
-        float spec = pow(max(dot(viewDir, reflectDir), 0.0), 32.);
-        vec3 specular = SPECULAR_STRENGTH * spec * lightColors[i] * lightStrength;
-
-        diffuse_acc += diffuse;
-        specular_acc += specular;
+        vec3 color = texelFetch(lights, 2 * int(i) + 1).rgb;
+        vec3 lit = shade(surface, norm, normalize(ray), viewDir, color);
+        light += lit * lightStrength * pointVisibility(i, ray);
     }
 
-    float dst = length(FragPos - viewPos);
-    float dst_decay = pow(DST_DECAY, -dst);
+    if (sunEnabled) {
+        vec3 lit = shade(surface, norm, -sunDirection, viewDir, sunColor);
+        light += lit * directionalStrength(surface.pbr) * sunVisibility(norm);
+    }
 
-    vec3 result = (1.0 - dst_decay) * fogColor
-        + dst_decay * (diffuse_acc + specular_acc + ambientColor) * objectColor;
+    float fog = fogFactor(length(FragPos - viewPos));
+    vec3 color = light + ambientColor * surface.albedo + Emissive;
+    vec3 result = (1.0 - fog) * fogColor + fog * color;
     FragColor = vec4(result, 1.0);
 }
\ No newline at end of file
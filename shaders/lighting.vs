@@ -1,17 +1,42 @@
 #version 330 core
 layout (location = 0) in vec3 aPos;
 layout (location = 1) in vec3 aNormal;
+layout (location = 2) in mat4 aModel;
+layout (location = 6) in vec3 aColor;
+layout (location = 7) in vec3 aSurface;
+layout (location = 8) in vec3 aEmissive;
+layout (location = 9) in vec2 aTexCoord;
+layout (location = 10) in vec3 aPbr;
 
 out vec3 FragPos;
 out vec3 Normal;
+out vec2 TexCoord;
+out float ViewDepth;
+// material factors, see material.rs
+flat out vec3 Albedo;
+flat out float Specular;
+flat out float Shininess;
+flat out float Metallic;
+flat out float Roughness;
+flat out vec3 Emissive;
+flat out int Pbr;
 
-uniform mat4 model;
 uniform mat4 view;
 uniform mat4 projection;
 
 void main() {
-    FragPos = vec3(model * vec4(aPos, 1.0));
-    Normal = mat3(transpose(inverse(model))) * aNormal;
+    FragPos = vec3(aModel * vec4(aPos, 1.0));
+    Normal = mat3(transpose(inverse(aModel))) * aNormal;
+    TexCoord = aTexCoord * aSurface.z;
+    Albedo = aColor;
+    Specular = aSurface.x;
+    Shininess = aSurface.y;
+    Metallic = aPbr.x;
+    Roughness = aPbr.y;
+    Emissive = aEmissive;
+    Pbr = int(aPbr.z);
 
-    gl_Position = projection * view * vec4(FragPos, 1.0);
+    vec4 viewPosition = view * vec4(FragPos, 1.0);
+    ViewDepth = -viewPosition.z;
+    gl_Position = projection * viewPosition;
 }
@@ -0,0 +1,28 @@
+#version 330 core
+layout (triangles) in;
+layout (line_strip, max_vertices = 6) out;
+
+in vec3 WorldNormal[];
+
+out vec3 LineColor;
+
+uniform mat4 view;
+uniform mat4 projection;
+uniform float normalLength;
+
+// one line per vertex, from the vertex along its world-space normal
+void main() {
+    for (int i = 0; i < 3; ++i) {
+        vec4 base = gl_in[i].gl_Position;
+        vec4 tip = base + vec4(WorldNormal[i] * normalLength, 0.0);
+        vec3 color = abs(WorldNormal[i]);
+
+        LineColor = color;
+        gl_Position = projection * view * base;
+        EmitVertex();
+        LineColor = color;
+        gl_Position = projection * view * tip;
+        EmitVertex();
+        EndPrimitive();
+    }
+}
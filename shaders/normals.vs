@@ -0,0 +1,11 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 1) in vec3 aNormal;
+layout (location = 2) in mat4 aModel;
+
+out vec3 WorldNormal;
+
+void main() {
+    WorldNormal = normalize(mat3(transpose(inverse(aModel))) * aNormal);
+    gl_Position = aModel * vec4(aPos, 1.0);
+}
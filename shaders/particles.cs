@@ -0,0 +1,61 @@
+#version 430 core
+// GROUP_SIZE and GRAVITY are injected by particles.rs
+layout (local_size_x = GROUP_SIZE) in;
+
+#include "include/particle.glsl"
+
+uniform float dt;
+uniform float time;
+uniform vec3 emitter;
+
+// top face of the floor; y points down
+const float FLOOR = 0.5;
+
+// uniform in [0, 1]
+float random(uint seed) {
+    seed ^= seed >> 16;
+    seed *= 0x7feb352du;
+    seed ^= seed >> 15;
+    seed *= 0x846ca68bu;
+    seed ^= seed >> 16;
+    return float(seed) / 4294967295.0;
+}
+
+// launches upwards from the emitter within a narrow cone
+void spawn(inout Particle p, uint seed) {
+    float phi = random(seed) * 6.2831853;
+    float spread = random(seed + 1u) * 0.35;
+    float speed = 10.0 + random(seed + 2u) * 6.0;
+    p.position.xyz = emitter;
+    p.velocity.xyz = vec3(cos(phi) * sin(spread), -cos(spread), sin(phi) * sin(spread)) * speed;
+}
+
+void main() {
+    uint i = gl_GlobalInvocationID.x;
+    if (i >= uint(particles.length())) {
+        return;
+    }
+
+    Particle p = particles[i];
+    float age = p.position.w + dt;
+    uint seed = i * 3u + floatBitsToUint(time) * 747796405u;
+
+    if (age >= p.velocity.w) {
+        age = 0.0;
+        spawn(p, seed);
+    } else if (p.position.w < 0.0 && age >= 0.0) {
+        spawn(p, seed);
+    } else if (age >= 0.0) {
+        p.velocity.y += GRAVITY * dt;
+        p.position.xyz += p.velocity.xyz * dt;
+
+        if (p.position.y > FLOOR && p.velocity.y > 0.0) {
+            p.position.y = FLOOR;
+            p.velocity.y *= -0.4;
+            p.velocity.xz *= 0.8;
+        }
+    }
+
+    p.position.w = age;
+    particles[i] = p;
+}
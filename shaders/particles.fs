@@ -0,0 +1,12 @@
+#version 430 core
+out vec4 FragColor;
+
+in float Life;
+
+uniform float intensity;
+
+// white-hot sparks cooling to red, added onto the HDR scene
+void main() {
+    vec3 color = mix(vec3(1.0, 0.2, 0.05), vec3(1.0, 0.8, 0.4), Life);
+    FragColor = vec4(color * intensity * Life, 1.0);
+}
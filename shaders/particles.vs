@@ -0,0 +1,17 @@
+#version 430 core
+out float Life;
+
+#include "include/particle.glsl"
+
+uniform mat4 view;
+uniform mat4 projection;
+
+void main() {
+    Particle p = particles[gl_VertexID];
+    Life = 1.0 - clamp(p.position.w / p.velocity.w, 0.0, 1.0);
+
+    // unborn particles are moved outside the clip volume
+    gl_Position = p.position.w < 0.0 ? vec4(2.0, 2.0, 2.0, 1.0)
+                                     : projection * view * vec4(p.position.xyz, 1.0);
+    gl_PointSize = 3.0;
+}
@@ -0,0 +1,12 @@
+#version 330 core
+layout (location = 0) in vec3 aPos;
+layout (location = 2) in mat4 aModel;
+
+out vec3 FragPos;
+
+uniform mat4 lightSpace;
+
+void main() {
+    FragPos = vec3(aModel * vec4(aPos, 1.0));
+    gl_Position = lightSpace * vec4(FragPos, 1.0);
+}
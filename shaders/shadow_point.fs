@@ -0,0 +1,10 @@
+#version 330 core
+in vec3 FragPos;
+
+uniform vec3 lightPos;
+uniform float far;
+
+// linear distance, so that lighting.fs can compare it for any direction
+void main() {
+    gl_FragDepth = length(FragPos - lightPos) / far;
+}
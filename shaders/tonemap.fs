@@ -0,0 +1,37 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+// REINHARD, ACES and EXPOSURE are injected by post/tonemap.rs
+uniform sampler2D hdr;
+uniform int toneMapper;
+uniform float exposure;
+
+// Narkowicz's fit of the ACES filmic curve
+vec3 aces(vec3 c) {
+    return clamp(c * (2.51 * c + 0.03) / (c * (2.43 * c + 0.59) + 0.14), 0.0, 1.0);
+}
+
+// the display's transfer function; the default framebuffer is not sRGB-capable
+// everywhere, so it is applied here rather than with GL_FRAMEBUFFER_SRGB
+vec3 linearToSrgb(vec3 c) {
+    c = clamp(c, 0.0, 1.0);
+    vec3 low = c * 12.92;
+    vec3 high = 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055;
+    return mix(low, high, step(0.0031308, c));
+}
+
+void main() {
+    vec3 color = texture(hdr, TexCoord).rgb * exposure;
+
+    if (toneMapper == REINHARD) {
+        color = color / (1.0 + color);
+    } else if (toneMapper == ACES) {
+        color = aces(color);
+    } else if (toneMapper == EXPOSURE) {
+        color = 1.0 - exp(-color);
+    }
+
+    FragColor = vec4(linearToSrgb(color), 1.0);
+}
@@ -0,0 +1,18 @@
+#version 330 core
+out vec4 FragColor;
+
+in vec2 TexCoord;
+
+uniform sampler2D image;
+uniform float radius;
+uniform float softness;
+uniform float strength;
+
+void main() {
+    vec2 size = vec2(textureSize(image, 0));
+    // distance from the center in halves of the frame height, so the shape is round
+    vec2 offset = (TexCoord - 0.5) * 2.0 * vec2(size.x / size.y, 1.0);
+    float darkening = smoothstep(radius, radius + softness, length(offset));
+    vec3 color = texture(image, TexCoord).rgb * (1.0 - strength * darkening);
+    FragColor = vec4(color, 1.0);
+}
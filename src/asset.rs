@@ -0,0 +1,48 @@
+pub mod gltf;
+pub mod obj;
+
+use crate::{
+    material::{Material, Shading},
+    model::{Model, PbrFactors},
+};
+use anyhow::{anyhow, Result};
+use glm::Mat4;
+use std::{error::Error, fmt::Display, path::Path};
+
+#[derive(Debug)]
+struct ParseError {
+    line: usize,
+    msg: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Loads an `.obj`, `.gltf` or `.glb` file into models, with `placement` applied
+/// on top of each model's own transform.
+pub fn load_models(path: impl AsRef<Path>, placement: Mat4) -> Result<Vec<Model>> {
+    let path = path.as_ref();
+
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("obj") => {
+            let mesh = obj::load(path)?.deduplicate();
+            let mut model = Model::new(&mesh, placement, PbrFactors::default());
+            // OBJ carries no PBR factors, so shade it classically from their approximation
+            model.set_material(Material {
+                shading: Shading::Classic,
+                ..model.material().clone()
+            });
+            Ok(vec![model])
+        }
+        Some("gltf" | "glb") => Ok(gltf::load(path)?
+            .into_iter()
+            .map(|p| Model::new(&p.mesh, placement * p.transform, p.factors))
+            .collect()),
+        _ => Err(anyhow!("unsupported model format: {}", path.display())),
+    }
+}
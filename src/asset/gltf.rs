@@ -0,0 +1,268 @@
+//! glTF 2.0 scenes (`.gltf` with external or embedded buffers, and `.glb`).
+//! Node hierarchies are flattened into one [`Primitive`] per mesh primitive with
+//! its world transform, keeping the file's index buffer. Only triangle lists are
+//! imported; textures, skins and animations are ignored.
+
+use crate::{
+    mesh::{self, IndexedMesh, Vertex},
+    model::PbrFactors,
+};
+use ::gltf::{mesh::Mode, Gltf, Node};
+use anyhow::{anyhow, Context, Result};
+use glm::{Mat4, Vec2, Vec3};
+use num_traits::{One, Zero};
+use std::path::Path;
+
+#[derive(Debug)]
+pub struct Primitive {
+    pub mesh: IndexedMesh,
+    pub transform: Mat4,
+    pub factors: PbrFactors,
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Vec<Primitive>> {
+    let path = path.as_ref();
+    let context = || format!("could not load {}", path.display());
+    let gltf = Gltf::open(path).with_context(context)?;
+    import(gltf, path.parent()).with_context(context)
+}
+
+/// Flattens the default scene, resolving external buffers relative to `base`.
+fn import(gltf: Gltf, base: Option<&Path>) -> Result<Vec<Primitive>> {
+    let Gltf { document, blob } = gltf;
+    let buffers = ::gltf::import_buffers(&document, base, blob)?;
+
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or(anyhow!("no scene"))?;
+
+    let mut primitives = Vec::new();
+
+    for node in scene.nodes() {
+        visit(&node, Mat4::one(), &buffers, &mut primitives)?;
+    }
+
+    Ok(primitives)
+}
+
+fn visit(
+    node: &Node,
+    parent: Mat4,
+    buffers: &[::gltf::buffer::Data],
+    primitives: &mut Vec<Primitive>,
+) -> Result<()> {
+    let [c0, c1, c2, c3] = node
+        .transform()
+        .matrix()
+        .map(|[x, y, z, w]| glm::vec4(x, y, z, w));
+    let transform = parent * Mat4::new(c0, c1, c2, c3);
+
+    for primitive in node.mesh().iter().flat_map(|mesh| mesh.primitives()) {
+        if primitive.mode() != Mode::Triangles {
+            continue;
+        }
+
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+
+        let positions: Vec<Vec3> = reader
+            .read_positions()
+            .ok_or(anyhow!("primitive without positions"))?
+            .map(|[x, y, z]| glm::vec3(x, y, z))
+            .collect();
+        let normals: Option<Vec<Vec3>> = reader
+            .read_normals()
+            .map(|normals| normals.map(|[x, y, z]| glm::vec3(x, y, z)).collect());
+        let tex_coords: Option<Vec<Vec2>> = reader.read_tex_coords(0).map(|tex_coords| {
+            tex_coords
+                .into_f32()
+                .map(|[u, v]| glm::vec2(u, v))
+                .collect()
+        });
+        let mut indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect(),
+        };
+        indices.truncate(indices.len() / 3 * 3);
+
+        if let Some(&i) = indices.iter().find(|&&i| i as usize >= positions.len()) {
+            Err(anyhow!("index {i} out of range"))?
+        }
+
+        let counts = [
+            ("NORMAL", normals.as_ref().map(Vec::len)),
+            ("TEXCOORD_0", tex_coords.as_ref().map(Vec::len)),
+        ];
+
+        for (name, count) in counts {
+            if let Some(count) = count.filter(|&count| count != positions.len()) {
+                Err(anyhow!(
+                    "{count} {name} values for {} positions",
+                    positions.len()
+                ))?
+            }
+        }
+
+        let vertex = |i: u32, normal: Vec3| {
+            let i = i as usize;
+            Vertex {
+                position: positions[i],
+                normal,
+                tex_coord: tex_coords.as_ref().map_or(Vec2::zero(), |t| t[i]),
+            }
+        };
+
+        let mesh = match &normals {
+            Some(normals) => IndexedMesh {
+                vertices: (0..positions.len() as u32)
+                    .map(|i| vertex(i, normals[i as usize]))
+                    .collect(),
+                indices,
+            },
+            // the spec asks for flat shading when normals are absent, so triangles
+            // cannot share vertices
+            None => {
+                let vertices: Vec<_> = indices
+                    .chunks_exact(3)
+                    .flat_map(|t| {
+                        let [a, b, c] = [t[0], t[1], t[2]].map(|i| positions[i as usize]);
+                        let flat = mesh::normalize_or_up(glm::cross(b - a, c - a));
+                        [vertex(t[0], flat), vertex(t[1], flat), vertex(t[2], flat)]
+                    })
+                    .collect();
+                IndexedMesh {
+                    indices: (0..vertices.len() as u32).collect(),
+                    vertices,
+                }
+            }
+        };
+
+        let material = primitive.material();
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, a] = pbr.base_color_factor();
+        let [er, eg, eb] = material.emissive_factor();
+
+        primitives.push(Primitive {
+            mesh,
+            transform,
+            factors: PbrFactors {
+                base_color: glm::vec4(r, g, b, a),
+                metallic: pbr.metallic_factor(),
+                roughness: pbr.roughness_factor(),
+                emissive: glm::vec3(er, eg, eb),
+            },
+        });
+    }
+
+    for child in node.children() {
+        visit(&child, transform, buffers, primitives)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A unit square, once as a line list and once as triangles without normals
+    /// (mesh 0), indexed with normals (mesh 1) and with an index past its corners
+    /// (mesh 2). The default scene nests meshes 0 and 1 below a translated root;
+    /// scene 1 holds the broken mesh.
+    const SQUARES: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }, { "nodes": [3] }],
+        "nodes": [
+            { "translation": [1, 0, 0], "children": [1] },
+            { "scale": [2, 2, 2], "mesh": 0, "children": [2] },
+            { "translation": [0, 0, 3], "mesh": 1 },
+            { "mesh": 2 }
+        ],
+        "meshes": [
+            { "primitives": [
+                { "attributes": { "POSITION": 0 }, "indices": 1, "mode": 1 },
+                { "attributes": { "POSITION": 0 }, "indices": 1 }
+            ] },
+            { "primitives": [{ "attributes": { "POSITION": 0, "NORMAL": 3 }, "indices": 1 }] },
+            { "primitives": [{ "attributes": { "POSITION": 0 }, "indices": 2 }] }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+              "min": [0, 0, 0], "max": [1, 1, 0] },
+            { "bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR" },
+            { "bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR" },
+            { "bufferView": 3, "componentType": 5126, "count": 4, "type": "VEC3" }
+        ],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 48 },
+            { "buffer": 0, "byteOffset": 48, "byteLength": 12 },
+            { "buffer": 0, "byteOffset": 60, "byteLength": 6 },
+            { "buffer": 0, "byteOffset": 68, "byteLength": 48 }
+        ],
+        "buffers": [{
+            "byteLength": 116,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAACAPwAAgD8AAAAAAAAAAAAAgD8AAAAAAAABAAIAAAACAAMAAAABAAQAAAAAAAAAAAAAAAAAgL8AAAAAAAAAAAAAgL8AAAAAAAAAAAAAgL8AAAAAAAAAAAAAgL8="
+        }]
+    }"#;
+
+    fn import_str(json: &str) -> Result<Vec<Primitive>> {
+        import(Gltf::from_slice(json.as_bytes())?, None)
+    }
+
+    fn normals(mesh: &IndexedMesh) -> Vec<Vec3> {
+        mesh.vertices.iter().map(|v| v.normal).collect()
+    }
+
+    #[test]
+    fn node_transforms_compose_down_the_hierarchy() {
+        let primitives = import_str(SQUARES).unwrap();
+        let point = |primitive: &Primitive, [x, y, z]: [f32; 3]| {
+            let v = primitive.transform * glm::vec4(x, y, z, 1.);
+            glm::vec3(v.x, v.y, v.z)
+        };
+
+        // scaled by node 1, then translated by the root
+        assert_eq!(point(&primitives[0], [1., 0., 0.]), glm::vec3(3., 0., 0.));
+        // node 2 translates within the scaled space of its parent
+        assert_eq!(point(&primitives[1], [0., 0., 0.]), glm::vec3(1., 0., 6.));
+    }
+
+    #[test]
+    fn non_triangle_primitives_are_skipped() {
+        let primitives = import_str(SQUARES).unwrap();
+
+        // the line list of mesh 0 is dropped, its triangles and mesh 1 remain
+        assert_eq!(primitives.len(), 2);
+    }
+
+    #[test]
+    fn primitives_with_normals_keep_their_index_buffer() {
+        let mesh = &import_str(SQUARES).unwrap()[1].mesh;
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices, [0, 1, 2, 0, 2, 3]);
+        assert_eq!(normals(mesh), [glm::vec3(0., 0., -1.); 4]);
+    }
+
+    #[test]
+    fn primitives_without_normals_are_flat_shaded() {
+        let mesh = &import_str(SQUARES).unwrap()[0].mesh;
+        let positions: Vec<Vec3> = mesh.vertices.iter().map(|v| v.position).collect();
+        let [a, b, c, d] =
+            [(0., 0.), (1., 0.), (1., 1.), (0., 1.)].map(|(x, y)| glm::vec3(x, y, 0.));
+
+        // every triangle gets its own corners, facing along the winding
+        assert_eq!(positions, [a, b, c, a, c, d]);
+        assert_eq!(mesh.indices, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(normals(mesh), [glm::vec3(0., 0., 1.); 6]);
+    }
+
+    #[test]
+    fn out_of_range_indices_are_reported() {
+        let broken = SQUARES.replace(r#""scene": 0"#, r#""scene": 1"#);
+        let e = import_str(&broken).unwrap_err();
+
+        assert_eq!(e.to_string(), "index 4 out of range");
+    }
+}
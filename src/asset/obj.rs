@@ -0,0 +1,313 @@
+//! Wavefront OBJ geometry. Materials (`mtllib`/`usemtl`), groups and objects are
+//! ignored; every face ends up in a single [`Mesh`].
+
+use crate::{
+    asset::ParseError,
+    mesh::{self, Mesh, Vertex},
+};
+use anyhow::{Context, Result};
+use glm::{Vec2, Vec3};
+use num_traits::Zero;
+use std::{collections::HashMap, fs, path::Path, str::SplitWhitespace};
+
+#[derive(Debug, Clone, Copy)]
+struct Corner {
+    position: usize,
+    tex_coord: Option<usize>,
+    normal: Option<usize>,
+}
+
+#[derive(Debug)]
+struct Triangle {
+    corners: [Corner; 3],
+    smoothing: u32,
+}
+
+pub fn load(path: impl AsRef<Path>) -> Result<Mesh> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)?;
+    parse(&source).with_context(|| format!("could not load {}", path.display()))
+}
+
+pub fn parse(source: &str) -> Result<Mesh> {
+    let mut positions = Vec::new();
+    let mut normals = Vec::new();
+    let mut tex_coords = Vec::new();
+    let mut triangles = Vec::new();
+    let mut smoothing = 0;
+
+    for (index, line) in source.lines().enumerate() {
+        let err = |msg: String| ParseError {
+            line: index + 1,
+            msg,
+        };
+        let line = line.split('#').next().unwrap_or_default();
+        let mut tokens = line.split_whitespace();
+
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        match keyword {
+            "v" => {
+                let [x, y, z] = floats(&mut tokens, [None; 3]).map_err(err)?;
+                positions.push(glm::vec3(x, y, z));
+            }
+            "vn" => {
+                let [x, y, z] = floats(&mut tokens, [None; 3]).map_err(err)?;
+                normals.push(glm::vec3(x, y, z));
+            }
+            "vt" => {
+                let [u, v] = floats(&mut tokens, [None, Some(0.)]).map_err(err)?;
+                tex_coords.push(glm::vec2(u, v));
+            }
+            "f" => {
+                let counts = (positions.len(), tex_coords.len(), normals.len());
+                let corners = tokens
+                    .map(|token| corner(token, counts))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(err)?;
+
+                if corners.len() < 3 {
+                    Err(err(format!("face with {} vertices", corners.len())))?
+                }
+
+                // fan triangulation, which assumes convex polygons
+                for i in 1..corners.len() - 1 {
+                    triangles.push(Triangle {
+                        corners: [corners[0], corners[i], corners[i + 1]],
+                        smoothing,
+                    });
+                }
+            }
+            "s" => {
+                smoothing = match tokens.next() {
+                    Some("off") => 0,
+                    Some(group) => group
+                        .parse()
+                        .map_err(|_| err(format!("invalid smoothing group {group}")))?,
+                    None => Err(err("missing smoothing group".into()))?,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    Ok(build(&positions, &normals, &tex_coords, &triangles))
+}
+
+/// Parses up to `N` floats; missing trailing values take their default, if any.
+fn floats<const N: usize>(
+    tokens: &mut SplitWhitespace,
+    defaults: [Option<f32>; N],
+) -> Result<[f32; N], String> {
+    let mut values = [0.; N];
+
+    for (value, default) in values.iter_mut().zip(defaults) {
+        *value = match tokens.next() {
+            Some(token) => token
+                .parse()
+                .map_err(|_| format!("invalid number {token}"))?,
+            None => default.ok_or(format!("expected {N} numbers"))?,
+        };
+    }
+
+    Ok(values)
+}
+
+/// Parses `v`, `v/vt`, `v//vn` or `v/vt/vn` into zero-based indices.
+fn corner(
+    token: &str,
+    (positions, tex_coords, normals): (usize, usize, usize),
+) -> Result<Corner, String> {
+    let mut parts = token.split('/');
+    let mut index = |count: usize| -> Result<Option<usize>, String> {
+        match parts.next() {
+            None | Some("") => Ok(None),
+            Some(part) => {
+                let i: isize = part.parse().map_err(|_| format!("invalid index {part}"))?;
+
+                // negative indices count back from the latest element
+                let resolved = match i {
+                    1.. => i - 1,
+                    ..0 => count as isize + i,
+                    0 => -1,
+                };
+
+                if (0..count as isize).contains(&resolved) {
+                    Ok(Some(resolved as _))
+                } else {
+                    Err(format!("index {i} out of range in {token}"))
+                }
+            }
+        }
+    };
+
+    Ok(Corner {
+        position: index(positions)?.ok_or(format!("missing position in {token}"))?,
+        tex_coord: index(tex_coords)?,
+        normal: index(normals)?,
+    })
+}
+
+/// Resolves corners into vertices. Corners without a normal get the face normal
+/// when their smoothing group is off, and otherwise the area-weighted average of
+/// all faces sharing the position within the same smoothing group.
+fn build(
+    positions: &[Vec3],
+    normals: &[Vec3],
+    tex_coords: &[Vec2],
+    triangles: &[Triangle],
+) -> Mesh {
+    let face_normal = |t: &Triangle| {
+        let [a, b, c] = t.corners.map(|c| positions[c.position]);
+        glm::cross(b - a, c - a)
+    };
+
+    let mut smooth: HashMap<(usize, u32), Vec3> = HashMap::new();
+
+    for t in triangles.iter().filter(|t| t.smoothing != 0) {
+        let n = face_normal(t);
+
+        for c in t.corners.iter().filter(|c| c.normal.is_none()) {
+            let acc = smooth
+                .entry((c.position, t.smoothing))
+                .or_insert(Vec3::zero());
+            *acc = *acc + n;
+        }
+    }
+
+    let vertices = triangles
+        .iter()
+        .flat_map(|t| {
+            let flat = mesh::normalize_or_up(face_normal(t));
+
+            t.corners.map(|c| Vertex {
+                position: positions[c.position],
+                normal: match c.normal {
+                    Some(i) => normals[i],
+                    None if t.smoothing == 0 => flat,
+                    None => mesh::normalize_or_up(smooth[&(c.position, t.smoothing)]),
+                },
+                tex_coord: c.tex_coord.map_or(Vec2::zero(), |i| tex_coords[i]),
+            })
+        })
+        .collect();
+
+    Mesh { vertices }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n";
+
+    fn positions(mesh: &Mesh) -> Vec<Vec3> {
+        mesh.vertices.iter().map(|v| v.position).collect()
+    }
+
+    fn error(source: &str) -> String {
+        parse(source).expect_err(source).to_string()
+    }
+
+    #[test]
+    fn negative_indices_count_back_from_the_latest_element() {
+        let mesh = parse(&format!("{SQUARE}f -4 -3 -2\nv 2 2 0\nf -1 -2 -3\n")).unwrap();
+
+        assert_eq!(
+            positions(&mesh),
+            [
+                glm::vec3(0., 0., 0.),
+                glm::vec3(1., 0., 0.),
+                glm::vec3(1., 1., 0.),
+                glm::vec3(2., 2., 0.),
+                glm::vec3(0., 1., 0.),
+                glm::vec3(1., 1., 0.),
+            ]
+        );
+    }
+
+    #[test]
+    fn corners_take_texture_coordinates_and_normals_when_given() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0.5 0.25\nvt 1\nvn 0 0 -1\n\
+                      f 1/1/1 2//1 3/2\n";
+        let vertices = parse(source).unwrap().vertices;
+        let flat = glm::vec3(0., 0., 1.);
+
+        assert_eq!(vertices.len(), 3);
+        assert_eq!(vertices[0].tex_coord, glm::vec2(0.5, 0.25));
+        assert_eq!(vertices[0].normal, glm::vec3(0., 0., -1.));
+        assert_eq!(vertices[1].tex_coord, glm::vec2(0., 0.));
+        assert_eq!(vertices[1].normal, glm::vec3(0., 0., -1.));
+        // a missing `v` of `vt` defaults to 0, a missing normal to the face's
+        assert_eq!(vertices[2].tex_coord, glm::vec2(1., 0.));
+        assert_eq!(vertices[2].normal, flat);
+    }
+
+    #[test]
+    fn polygons_are_fan_triangulated() {
+        let mesh = parse(&format!("{SQUARE}f 1 2 3 4\n")).unwrap();
+        let [a, b, c, d] =
+            [(0., 0.), (1., 0.), (1., 1.), (0., 1.)].map(|(x, y)| glm::vec3(x, y, 0.));
+
+        assert_eq!(positions(&mesh), [a, b, c, a, c, d]);
+
+        let pentagon = parse(&format!("{SQUARE}v 0.5 1.5 0\nf 1 2 3 5 4\n")).unwrap();
+        assert_eq!(pentagon.vertices.len(), 9);
+    }
+
+    #[test]
+    fn smoothing_groups_average_normals() {
+        // two faces folded along the edge from vertex 1 to vertex 2
+        let folded = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\n";
+        let smooth = parse(&format!("{folded}s 1\nf 1 2 3\nf 1 4 2\n")).unwrap();
+        let flat = parse(&format!("{folded}s off\nf 1 2 3\nf 1 4 2\n")).unwrap();
+
+        assert_eq!(flat.vertices[0].normal, glm::vec3(0., 0., 1.));
+        assert_eq!(flat.vertices[3].normal, glm::vec3(0., 1., 0.));
+        assert_eq!(smooth.vertices[0].normal, smooth.vertices[3].normal);
+        assert_eq!(
+            smooth.vertices[0].normal,
+            glm::normalize(glm::vec3(0., 1., 1.))
+        );
+    }
+
+    #[test]
+    fn malformed_lines_report_their_line() {
+        assert_eq!(error("v 1 2\n"), "line 1: expected 3 numbers");
+        assert_eq!(error("v 0 0 0\nv 1 x 0\n"), "line 2: invalid number x");
+        assert_eq!(
+            error(&format!("{SQUARE}f 1 2\n")),
+            "line 5: face with 2 vertices"
+        );
+        assert_eq!(
+            error(&format!("{SQUARE}f 1 2 5\n")),
+            "line 5: index 5 out of range in 5"
+        );
+        assert_eq!(
+            error(&format!("{SQUARE}f 0 1 2\n")),
+            "line 5: index 0 out of range in 0"
+        );
+        assert_eq!(
+            error(&format!("{SQUARE}f 1 2 3/1\n")),
+            "line 5: index 1 out of range in 3/1"
+        );
+        assert_eq!(
+            error(&format!("{SQUARE}f 1 2 /1\n")),
+            "line 5: missing position in /1"
+        );
+        assert_eq!(error("s\n"), "line 1: missing smoothing group");
+        assert_eq!(error("s x\n"), "line 1: invalid smoothing group x");
+    }
+
+    #[test]
+    fn comments_and_unknown_keywords_are_ignored() {
+        let mesh = parse(&format!(
+            "# a square\nmtllib x.mtl\n{SQUARE}o square\nf 1 2 3 # first\n"
+        ))
+        .unwrap();
+
+        assert_eq!(mesh.vertices.len(), 3);
+    }
+}
@@ -10,6 +10,12 @@ pub struct Camera {
     pub phi: f32,
 }
 
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Camera {
     const SPEED: f32 = 8.;
     const SENSITIVITY: f32 = 1.0E-3;
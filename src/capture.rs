@@ -0,0 +1,151 @@
+//! Screenshots and numbered frame sequences read back from the window.
+
+use crate::util;
+use anyhow::Result;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+pub struct Capture {
+    dir: PathBuf,
+    recording: Option<Recording>,
+}
+
+struct Recording {
+    dir: PathBuf,
+    /// Scene time of the first frame.
+    start: f32,
+    frame: u32,
+}
+
+impl Capture {
+    /// Simulated time between recorded frames, independent of the display rate.
+    pub const RECORD_DELTA: f32 = 1. / 60.;
+
+    /// Captures are written below `dir`, which is created on the first one.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            recording: None,
+        }
+    }
+
+    /// Saves the back buffer of the window as `screenshot_<timestamp>.png`.
+    /// Call after drawing and before presenting.
+    pub fn screenshot(&self, width: u32, height: u32) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.dir.join(format!("screenshot_{}.png", timestamp()));
+        save_window(&path, width, height)?;
+        Ok(path)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Starts writing every frame into a new `recording_<timestamp>/`
+    /// directory, with the scene advancing from `t` by [`Self::RECORD_DELTA`].
+    pub fn start_recording(&mut self, t: f32) -> Result<PathBuf> {
+        let dir = self.dir.join(format!("recording_{}", timestamp()));
+        fs::create_dir_all(&dir)?;
+        self.recording = Some(Recording {
+            dir: dir.clone(),
+            start: t,
+            frame: 0,
+        });
+        Ok(dir)
+    }
+
+    /// Returns the number of frames written.
+    pub fn stop_recording(&mut self) -> Option<u32> {
+        self.recording.take().map(|recording| recording.frame)
+    }
+
+    /// Time step and scene time of the next recorded frame.
+    pub fn timestep(&self) -> Option<(f32, f32)> {
+        self.recording.as_ref().map(|recording| {
+            let t = recording.start + recording.frame as f32 * Self::RECORD_DELTA;
+            (Self::RECORD_DELTA, t)
+        })
+    }
+
+    /// Saves the back buffer as `frame_NNNNN.png` while recording. Call after
+    /// drawing and before presenting.
+    pub fn record(&mut self, width: u32, height: u32) -> Result<()> {
+        if let Some(recording) = &mut self.recording {
+            let path = recording
+                .dir
+                .join(format!("frame_{:05}.png", recording.frame));
+            save_window(&path, width, height)?;
+            recording.frame += 1;
+        }
+
+        Ok(())
+    }
+}
+
+fn save_window(path: &Path, width: u32, height: u32) -> Result<()> {
+    unsafe {
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+        gl::ReadBuffer(gl::BACK);
+    }
+
+    util::save_png(path, width, height, &util::read_pixels(width, height))
+}
+
+/// Current UTC time as `YYYYMMDD-HHMMSS-mmm`, which sorts chronologically.
+fn timestamp() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format_timestamp(now)
+}
+
+/// Formats a time since the Unix epoch like [`timestamp`].
+fn format_timestamp(since_epoch: Duration) -> String {
+    let secs = since_epoch.as_secs();
+    let (days, secs) = ((secs / 86_400) as i64, secs % 86_400);
+
+    // days since 1970-01-01 to a civil date, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}{month:02}{day:02}-{:02}{:02}{:02}-{:03}",
+        secs / 3_600,
+        secs / 60 % 60,
+        secs % 60,
+        since_epoch.subsec_millis(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_timestamp_converts_days_to_civil_dates() {
+        let at = |secs: u64, millis: u32| format_timestamp(Duration::new(secs, millis * 1_000_000));
+
+        assert_eq!(at(0, 0), "19700101-000000-000");
+        assert_eq!(at(86_399, 999), "19700101-235959-999");
+        // leap day of a year divisible by 400, then the day after
+        assert_eq!(at(951_782_400, 0), "20000229-000000-000");
+        assert_eq!(at(951_868_800, 0), "20000301-000000-000");
+        // end of a leap year
+        assert_eq!(at(1_735_689_599, 0), "20241231-235959-000");
+        assert_eq!(at(1_792_354_245, 123), "20261018-201045-123");
+        // 2100 is not a leap year
+        assert_eq!(at(4_107_542_400, 0), "21000301-000000-000");
+    }
+}
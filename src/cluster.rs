@@ -0,0 +1,148 @@
+//! Clustered forward shading: lights are binned on the CPU into a view-space grid
+//! of tiles and exponential depth slices, and each fragment only visits the lights
+//! of its cluster.
+
+use crate::shader::{shader_program::ShaderProgram, texture_buffer::TextureBuffer};
+use gl::types::GLuint;
+use glm::{Mat4, Vec3};
+
+#[derive(Debug)]
+pub struct ClusterGrid {
+    near: f32,
+    far: f32,
+    /// `(offset, count)` into `indices` for every cluster, x-major.
+    table: Vec<[u32; 2]>,
+    indices: Vec<u32>,
+    pairs: Vec<(u32, u32)>,
+    table_buffer: TextureBuffer,
+    index_buffer: TextureBuffer,
+}
+
+impl ClusterGrid {
+    const DIMS: [usize; 3] = [16, 9, 24];
+    const CLUSTERS: usize = Self::DIMS[0] * Self::DIMS[1] * Self::DIMS[2];
+    const TABLE_UNIT: GLuint = 0;
+    const INDEX_UNIT: GLuint = 1;
+
+    pub fn new() -> Self {
+        Self {
+            near: 0.,
+            far: 0.,
+            table: vec![[0; 2]; Self::CLUSTERS],
+            indices: Vec::new(),
+            pairs: Vec::new(),
+            table_buffer: TextureBuffer::new(gl::RG32UI),
+            index_buffer: TextureBuffer::new(gl::R32UI),
+        }
+    }
+
+    /// Bins every light whose sphere of influence touches a cluster and uploads
+    /// the result. `projection` must be a perspective projection.
+    pub fn update(&mut self, view: &Mat4, projection: &Mat4, positions: &[Vec3], radius: f32) {
+        let [nx, ny, _] = Self::DIMS;
+
+        // recover the frustum from the matrix so that it always matches the scene
+        let (sx, sy) = (projection[0].x, projection[1].y);
+        let (p22, p32) = (projection[2].z, projection[3].z);
+        self.near = p32 / (p22 - 1.);
+        self.far = p32 / (p22 + 1.);
+
+        self.pairs.clear();
+
+        for (light, &p) in positions.iter().enumerate() {
+            let v = *view * glm::vec4(p.x, p.y, p.z, 1.);
+            let depth = -v.z;
+
+            if depth + radius < self.near || depth - radius > self.far {
+                continue;
+            }
+
+            let (za, zb) = (
+                (depth - radius).max(self.near),
+                (depth + radius).min(self.far),
+            );
+
+            // the sphere's extent is widest on screen where it is closest
+            let tiles = |center: f32, scale: f32, count: usize| {
+                let (lo, hi) = (center - radius, center + radius);
+                let lo = lo * scale / if lo < 0. { za } else { zb };
+                let hi = hi * scale / if hi > 0. { za } else { zb };
+                let tile = |ndc: f32| {
+                    (((ndc + 1.) * 0.5 * count as f32).floor()).clamp(0., count as f32 - 1.)
+                        as usize
+                };
+                tile(lo)..=tile(hi)
+            };
+
+            for z in self.slice(za)..=self.slice(zb) {
+                for y in tiles(v.y, sy, ny) {
+                    for x in tiles(v.x, sx, nx) {
+                        let cluster = x + nx * (y + ny * z);
+                        self.pairs.push((cluster as _, light as _));
+                    }
+                }
+            }
+        }
+
+        // counting sort of the pairs by cluster
+        self.table.fill([0; 2]);
+
+        for &(cluster, _) in &self.pairs {
+            self.table[cluster as usize][1] += 1;
+        }
+
+        let mut offset = 0;
+
+        for entry in &mut self.table {
+            entry[0] = offset;
+            offset += entry[1];
+            entry[1] = 0;
+        }
+
+        self.indices.resize(self.pairs.len(), 0);
+
+        for &(cluster, light) in &self.pairs {
+            let [offset, count] = &mut self.table[cluster as usize];
+            self.indices[(*offset + *count) as usize] = light;
+            *count += 1;
+        }
+
+        self.table_buffer.upload(&self.table);
+        self.index_buffer.upload(&self.indices);
+    }
+
+    /// Binds the tables and sets the uniforms `lighting.fs` uses to find its cluster,
+    /// with tiles spanning the current viewport.
+    pub fn bind(&self, program: &ShaderProgram) {
+        let [nx, ny, nz] = Self::DIMS;
+        let mut viewport = [0; 4];
+
+        unsafe {
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+
+        self.table_buffer.bind(Self::TABLE_UNIT);
+        self.index_buffer.bind(Self::INDEX_UNIT);
+        program.set_i32(c"clusterTable", Self::TABLE_UNIT as _);
+        program.set_i32(c"clusterIndices", Self::INDEX_UNIT as _);
+        program.set_uvec3(c"clusterDims", glm::Vector3::new(nx as _, ny as _, nz as _));
+        program.set_vec2(
+            c"clusterScreen",
+            glm::vec2(viewport[2] as f32, viewport[3] as f32),
+        );
+        program.set_f32(c"clusterNear", self.near);
+        program.set_f32(c"clusterScale", nz as f32 / (self.far / self.near).ln());
+    }
+
+    fn slice(&self, depth: f32) -> usize {
+        let nz = Self::DIMS[2];
+        let slice = (depth / self.near).ln() / (self.far / self.near).ln() * nz as f32;
+        (slice.floor().max(0.) as usize).min(nz - 1)
+    }
+}
+
+impl Default for ClusterGrid {
+    fn default() -> Self {
+        Self::new()
+    }
+}
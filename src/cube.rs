@@ -1,8 +1,4 @@
-use crate::{
-    graphics::{Graphics, Lighting},
-    shader::shader_program::ShaderProgram,
-    util,
-};
+use crate::{material::Material, util};
 use glm::ext as gle;
 use glm::{Mat4, Vec3};
 use num_traits::{One, Zero};
@@ -16,10 +12,26 @@ pub struct Cube {
     rotation: f32,
     rot_speed: f32,
     scale: f32,
+    pub material: Material,
 }
 
 impl Cube {
-    pub fn new() -> Self {
+    /// A cube launched from the middle of the floor.
+    pub fn new(rng: &mut impl Rng, material: Material) -> Self {
+        let mut cube = Self {
+            position: Vec3::zero(),
+            velocity: Vec3::zero(),
+            rot_axis: Vec3::one(),
+            rotation: 0.,
+            rot_speed: 0.,
+            scale: 0.75,
+            material,
+        };
+        cube.launch(rng);
+        cube
+    }
+
+    fn launch(&mut self, rng: &mut impl Rng) {
         let uni_r = Uniform::new(12., 24.);
         let uni_phi = Uniform::new(glm::radians(-180.), glm::radians(180.));
         let uni_theta = Uniform::new(glm::radians(-90.), glm::radians(-60.));
@@ -27,7 +39,6 @@ impl Cube {
         let uni_rot_theta = Uniform::new(glm::radians(-90.), glm::radians(90.));
         let uni_rot_speed = Uniform::new(0., 8.);
 
-        let mut rng = rand::thread_rng();
         let mut f = |u| rng.sample(u);
         let r: f32 = f(uni_r);
         let phi: f32 = f(uni_phi);
@@ -36,17 +47,14 @@ impl Cube {
         let rot_theta: f32 = f(uni_rot_theta);
         let rot_speed: f32 = f(uni_rot_speed);
 
-        Self {
-            position: glm::vec3(0., -1., 0.),
-            velocity: util::sphere(theta, phi) * r,
-            rot_axis: util::sphere(rot_theta, rot_phi),
-            rotation: 0.,
-            rot_speed,
-            scale: 0.75,
-        }
+        self.position = glm::vec3(0., -1., 0.);
+        self.velocity = util::sphere(theta, phi) * r;
+        self.rot_axis = util::sphere(rot_theta, rot_phi);
+        self.rotation = 0.;
+        self.rot_speed = rot_speed;
     }
 
-    pub fn floor() -> Self {
+    pub fn floor(material: Material) -> Self {
         Self {
             position: glm::vec3(0., 500.5, 0.),
             velocity: Vec3::zero(),
@@ -54,12 +62,13 @@ impl Cube {
             rotation: 0.,
             rot_speed: 0.,
             scale: 1000.,
+            material,
         }
     }
 
-    pub fn update(&mut self, dt: f32, force: Vec3) {
+    pub fn update(&mut self, dt: f32, force: Vec3, rng: &mut impl Rng) {
         if self.position.y > 5. {
-            *self = Cube::new();
+            self.launch(rng);
         }
 
         const GRAVITY: f32 = 10.;
@@ -68,11 +77,9 @@ impl Cube {
         self.rotation += self.rot_speed * dt;
     }
 
-    pub fn draw(&self, lighting: &Graphics<Lighting>, shader_lighting: &ShaderProgram) {
+    pub fn model(&self) -> Mat4 {
         let model = gle::translate(&Mat4::one(), self.position);
         let model = gle::scale(&model, Vec3::one() * self.scale);
-        let model = gle::rotate(&model, self.rotation, self.rot_axis);
-        shader_lighting.set_mat4(c"model", &model);
-        lighting.draw();
+        gle::rotate(&model, self.rotation, self.rot_axis)
     }
 }
@@ -0,0 +1,200 @@
+//! Deferred shading: geometry is written into a G-buffer once, then every light
+//! is accumulated by drawing a cube around its sphere of influence.
+
+use crate::{
+    graphics::{self, Fullscreen, Graphics, Instance, LightCube},
+    lights,
+    render_target::{Format, Framebuffer, RenderTarget, RenderTargetBuilder},
+    renderer::CameraUniforms,
+    scene::Scene,
+    shader::{preprocessor::Defines, shader_program::ShaderProgram, uniforms::Uniforms},
+    texture::Filter,
+};
+use anyhow::Result;
+use glm::ext as gle;
+use glm::{Mat4, Vec3};
+use num_traits::One;
+use std::iter;
+
+/// G-buffer samplers are set to the units of [`Deferred::bind_gbuffer`].
+#[derive(Uniforms)]
+struct AmbientUniforms {
+    g_position: i32,
+    g_albedo_spec: i32,
+    g_emissive: i32,
+    g_depth: i32,
+    view_pos: Vec3,
+    fog_color: Vec3,
+    ambient_color: Vec3,
+}
+
+#[derive(Uniforms)]
+struct LightVolumeUniforms {
+    #[uniform(flatten)]
+    camera: CameraUniforms,
+    g_position: i32,
+    g_normal: i32,
+    g_albedo_spec: i32,
+    g_emissive: i32,
+    view_pos: Vec3,
+}
+
+pub struct Deferred {
+    /// Position, normal with shininess (roughness with PBR shading) in alpha,
+    /// albedo with specular strength (metallic) in alpha, emissive with 1 for
+    /// PBR shading in alpha, and depth.
+    gbuffer: RenderTarget,
+    shader_gbuffer: ShaderProgram,
+    shader_ambient: ShaderProgram,
+    shader_light: ShaderProgram,
+    volumes: Graphics<LightCube>,
+    fullscreen: Fullscreen,
+    instances: Vec<Instance>,
+    /// Bound at `begin`, shaded into by `resolve`.
+    target: Framebuffer,
+}
+
+impl Deferred {
+    /// `defines` are those of `lighting.fs`.
+    pub fn new(width: u32, height: u32, defines: &Defines) -> Result<Self> {
+        let program = |vs, fs| ShaderProgram::with_defines(vs, fs, defines);
+
+        Ok(Self {
+            gbuffer: RenderTargetBuilder::new(width, height)
+                .color(Format::Rgba16F)
+                .color(Format::Rgba16F)
+                .color(Format::Rgba8)
+                .color(Format::Rgba16F)
+                .depth(Format::Depth24)
+                .filter(Filter::Nearest)
+                .build()?,
+            shader_gbuffer: program("lighting.vs", "gbuffer.fs")?,
+            shader_ambient: program("fullscreen.vs", "deferred_ambient.fs")?,
+            shader_light: program("deferred_light.vs", "deferred_light.fs")?,
+            volumes: Graphics::<LightCube>::new(graphics::CUBE_VERTICES)
+                .with_indices(graphics::CUBE_INDICES)
+                .with_instances(&Instance::layout()),
+            fullscreen: Fullscreen::new(),
+            instances: Vec::new(),
+            target: Framebuffer::default(),
+        })
+    }
+
+    /// See [`ShaderProgram::reload`].
+    pub fn reload_shaders(&mut self) {
+        self.shader_gbuffer.reload();
+        self.shader_ambient.reload();
+        self.shader_light.reload();
+    }
+
+    /// See [`ShaderProgram::check_uniforms`].
+    pub fn check_uniforms(&self) {
+        self.shader_gbuffer.check_uniforms();
+        self.shader_ambient.check_uniforms();
+        self.shader_light.check_uniforms();
+    }
+
+    /// Redirects drawing into the G-buffer. Everything drawn until `resolve` must
+    /// use the vertex format of `Graphics<Lighting>`.
+    pub fn begin(&mut self, scene: &Scene) {
+        self.target = Framebuffer::current();
+        self.gbuffer.bind();
+
+        unsafe {
+            gl::ClearColor(0., 0., 0., 0.);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+
+        self.shader_gbuffer.enable();
+        CameraUniforms::new(scene).apply(&self.shader_gbuffer);
+    }
+
+    /// The program bound by `begin`, for setting the material maps.
+    pub fn geometry_program(&self) -> &ShaderProgram {
+        &self.shader_gbuffer
+    }
+
+    /// Reallocates the G-buffer; `width` and `height` as for [`Self::new`].
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        self.gbuffer.resize(width, height)
+    }
+
+    /// Binds the color attachments to texture units 0 to 3 in declaration order
+    /// and depth to unit 4.
+    fn bind_gbuffer(&self) {
+        for index in 0..4 {
+            self.gbuffer.bind_color(index, index as _);
+        }
+
+        self.gbuffer.bind_depth(4);
+    }
+
+    /// Shades the G-buffer into the framebuffer that was bound at `begin`,
+    /// leaving the scene depth there for forward passes.
+    pub fn resolve(&mut self, scene: &Scene) {
+        self.target.bind();
+
+        unsafe {
+            gl::DepthFunc(gl::ALWAYS);
+        }
+
+        self.bind_gbuffer();
+
+        let shader_ambient = &self.shader_ambient;
+        shader_ambient.enable();
+        AmbientUniforms {
+            g_position: 0,
+            g_albedo_spec: 2,
+            g_emissive: 3,
+            g_depth: 4,
+            view_pos: scene.camera.position,
+            fog_color: scene.fog_color,
+            ambient_color: scene.ambient_color,
+        }
+        .apply(shader_ambient);
+        self.fullscreen.draw();
+
+        let diameter = 2. * lights::radius();
+        self.instances.clear();
+        self.instances
+            .extend(iter::zip(&scene.light_positions, &scene.light_colors).map(
+                |(&pos, &color)| {
+                    let model =
+                        gle::scale(&gle::translate(&Mat4::one(), pos), Vec3::one() * diameter);
+                    Instance::new(&model, color)
+                },
+            ));
+        self.volumes.set_instances(&self.instances);
+
+        unsafe {
+            // back faces only, so that every covered pixel is shaded once per light,
+            // even with the camera inside the volume
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::CULL_FACE);
+            gl::CullFace(gl::FRONT);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+        }
+
+        let shader_light = &self.shader_light;
+        shader_light.enable();
+        LightVolumeUniforms {
+            camera: CameraUniforms::new(scene),
+            g_position: 0,
+            g_normal: 1,
+            g_albedo_spec: 2,
+            g_emissive: 3,
+            view_pos: scene.camera.position,
+        }
+        .apply(shader_light);
+        self.volumes.draw_instanced();
+
+        unsafe {
+            gl::Disable(gl::BLEND);
+            gl::Disable(gl::CULL_FACE);
+            gl::CullFace(gl::BACK);
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(gl::LESS);
+        }
+    }
+}
@@ -1,21 +1,30 @@
-use gl::types::{GLsizei, GLuint};
-use std::marker::PhantomData;
-
-const F32_SIZE: GLsizei = size_of::<f32>() as _;
-pub const CUBE: &[f32] = &[
-    -0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.5, 0.5, -0.5, 0.0, 0.0,
-    -1.0, 0.5, 0.5, -0.5, 0.0, 0.0, -1.0, -0.5, 0.5, -0.5, 0.0, 0.0, -1.0, -0.5, -0.5, -0.5, 0.0,
-    0.0, -1.0, -0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 0.5, 0.5, 0.5, 0.0,
-    0.0, 1.0, 0.5, 0.5, 0.5, 0.0, 0.0, 1.0, -0.5, 0.5, 0.5, 0.0, 0.0, 1.0, -0.5, -0.5, 0.5, 0.0,
-    0.0, 1.0, -0.5, 0.5, 0.5, -1.0, 0.0, 0.0, -0.5, 0.5, -0.5, -1.0, 0.0, 0.0, -0.5, -0.5, -0.5,
-    -1.0, 0.0, 0.0, -0.5, -0.5, -0.5, -1.0, 0.0, 0.0, -0.5, -0.5, 0.5, -1.0, 0.0, 0.0, -0.5, 0.5,
-    0.5, -1.0, 0.0, 0.0, 0.5, 0.5, 0.5, 1.0, 0.0, 0.0, 0.5, 0.5, -0.5, 1.0, 0.0, 0.0, 0.5, -0.5,
-    -0.5, 1.0, 0.0, 0.0, 0.5, -0.5, -0.5, 1.0, 0.0, 0.0, 0.5, -0.5, 0.5, 1.0, 0.0, 0.0, 0.5, 0.5,
-    0.5, 1.0, 0.0, 0.0, -0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.5,
-    -0.5, 0.5, 0.0, -1.0, 0.0, 0.5, -0.5, 0.5, 0.0, -1.0, 0.0, -0.5, -0.5, 0.5, 0.0, -1.0, 0.0,
-    -0.5, -0.5, -0.5, 0.0, -1.0, 0.0, -0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 0.5, 0.5, -0.5, 0.0, 1.0,
-    0.0, 0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 0.5, 0.5, 0.5, 0.0, 1.0, 0.0, -0.5, 0.5, 0.5, 0.0, 1.0, 0.0,
-    -0.5, 0.5, -0.5, 0.0, 1.0, 0.0,
+pub mod layout;
+
+use crate::material::{Material, Shading};
+use gl::types::{GLenum, GLuint};
+use glm::{Mat4, Vec3};
+use layout::{Attribute, VertexLayout};
+use std::{array, marker::PhantomData, ptr};
+
+const F32_SIZE: usize = size_of::<f32>();
+/// Position, normal and texture coordinates of a unit cube, 24 vertices.
+pub const CUBE_VERTICES: &[f32] = &[
+    -0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 0.0, 0.0, 0.5, -0.5, -0.5, 0.0, 0.0, -1.0, 1.0, 0.0, 0.5,
+    0.5, -0.5, 0.0, 0.0, -1.0, 1.0, 1.0, -0.5, 0.5, -0.5, 0.0, 0.0, -1.0, 0.0, 1.0, -0.5, -0.5,
+    0.5, 0.0, 0.0, 1.0, 0.0, 0.0, 0.5, -0.5, 0.5, 0.0, 0.0, 1.0, 1.0, 0.0, 0.5, 0.5, 0.5, 0.0, 0.0,
+    1.0, 1.0, 1.0, -0.5, 0.5, 0.5, 0.0, 0.0, 1.0, 0.0, 1.0, -0.5, 0.5, 0.5, -1.0, 0.0, 0.0, 1.0,
+    1.0, -0.5, 0.5, -0.5, -1.0, 0.0, 0.0, 0.0, 1.0, -0.5, -0.5, -0.5, -1.0, 0.0, 0.0, 0.0, 0.0,
+    -0.5, -0.5, 0.5, -1.0, 0.0, 0.0, 1.0, 0.0, 0.5, 0.5, 0.5, 1.0, 0.0, 0.0, 1.0, 1.0, 0.5, 0.5,
+    -0.5, 1.0, 0.0, 0.0, 0.0, 1.0, 0.5, -0.5, -0.5, 1.0, 0.0, 0.0, 0.0, 0.0, 0.5, -0.5, 0.5, 1.0,
+    0.0, 0.0, 1.0, 0.0, -0.5, -0.5, -0.5, 0.0, -1.0, 0.0, 0.0, 0.0, 0.5, -0.5, -0.5, 0.0, -1.0,
+    0.0, 1.0, 0.0, 0.5, -0.5, 0.5, 0.0, -1.0, 0.0, 1.0, 1.0, -0.5, -0.5, 0.5, 0.0, -1.0, 0.0, 0.0,
+    1.0, -0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 0.0, 0.0, 0.5, 0.5, -0.5, 0.0, 1.0, 0.0, 1.0, 0.0, 0.5,
+    0.5, 0.5, 0.0, 1.0, 0.0, 1.0, 1.0, -0.5, 0.5, 0.5, 0.0, 1.0, 0.0, 0.0, 1.0,
+];
+/// Counter-clockwise when seen from outside the cube.
+pub const CUBE_INDICES: &[u16] = &[
+    0, 2, 1, 2, 0, 3, 4, 5, 6, 6, 7, 4, 8, 9, 10, 10, 11, 8, 12, 14, 13, 14, 12, 15, 16, 17, 18,
+    18, 19, 16, 20, 22, 21, 22, 20, 23,
 ];
 
 #[derive(Debug)]
@@ -23,25 +32,157 @@ pub struct Graphics<T> {
     vbo: GLuint,
     vao: GLuint,
     vertex_count: usize,
+    elements: Option<Elements>,
+    instances: Option<Instances>,
     _t: PhantomData<T>,
 }
 
+#[derive(Debug)]
+struct Elements {
+    ebo: GLuint,
+    count: usize,
+    kind: GLenum,
+}
+
+#[derive(Debug)]
+struct Instances {
+    vbo: GLuint,
+    count: usize,
+    /// Of the layout given to `with_instances`, checked by `set_instances`.
+    stride: usize,
+}
+
+/// Per-instance data for both vertex formats: a model matrix at locations 2 to 5,
+/// a color (the albedo of lit geometry) at location 6, then specular strength,
+/// shininess and tiling at location 7, the emissive color at location 8 and
+/// metallic, roughness and the shading model (1 for PBR) at location 10.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Instance {
+    pub model: [[f32; 4]; 4],
+    pub color: [f32; 3],
+    pub surface: [f32; 3],
+    pub emissive: [f32; 3],
+    pub pbr: [f32; 3],
+}
+
+// all fields are `f32`, so there is no padding
+unsafe impl Pod for Instance {}
+
+impl Instance {
+    /// An instance whose material factors are those of [`Material::default`].
+    pub fn new(model: &Mat4, color: Vec3) -> Self {
+        Self::with_material(
+            model,
+            &Material {
+                albedo: color,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn with_material(model: &Mat4, material: &Material) -> Self {
+        let (color, emissive) = (material.albedo, material.emissive);
+
+        Self {
+            model: array::from_fn(|i| {
+                let c = model[i];
+                [c.x, c.y, c.z, c.w]
+            }),
+            color: [color.x, color.y, color.z],
+            surface: [material.specular, material.shininess, material.tiling],
+            emissive: [emissive.x, emissive.y, emissive.z],
+            pbr: [
+                material.metallic,
+                material.roughness,
+                match material.shading {
+                    Shading::Classic => 0.,
+                    Shading::Pbr => 1.,
+                },
+            ],
+        }
+    }
+
+    pub fn layout() -> VertexLayout {
+        let column = 4 * F32_SIZE;
+
+        VertexLayout::new(size_of::<Self>())
+            .attribute(Attribute::float(2, 4, 0))
+            .attribute(Attribute::float(3, 4, column))
+            .attribute(Attribute::float(4, 4, 2 * column))
+            .attribute(Attribute::float(5, 4, 3 * column))
+            .attribute(Attribute::float(6, 3, 4 * column))
+            .attribute(Attribute::float(7, 3, 4 * column + 3 * F32_SIZE))
+            .attribute(Attribute::float(8, 3, 4 * column + 6 * F32_SIZE))
+            .attribute(Attribute::float(10, 3, 4 * column + 9 * F32_SIZE))
+            .divisor(1)
+    }
+}
+
+/// Position, normal and texture coordinates (at location 9), interleaved as in
+/// [`CUBE_VERTICES`].
 #[derive(Debug)]
 pub struct Lighting;
+/// Position only, reading the same buffers as [`Lighting`].
 #[derive(Debug)]
 pub struct LightCube;
 
-impl<T> Graphics<T> {
-    pub fn draw(&self) {
-        unsafe {
-            gl::BindVertexArray(self.vao);
-            gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count as _);
-        }
+/// Ties a marker type to the vertex layout `Graphics::new` uses for it.
+pub trait VertexFormat {
+    fn layout() -> VertexLayout;
+}
+
+impl VertexFormat for Lighting {
+    fn layout() -> VertexLayout {
+        VertexLayout::new(8 * F32_SIZE)
+            .attribute(Attribute::float(0, 3, 0))
+            .attribute(Attribute::float(1, 3, 3 * F32_SIZE))
+            .attribute(Attribute::float(9, 2, 6 * F32_SIZE))
     }
 }
 
-impl Graphics<Lighting> {
-    pub fn new(vertices: &[f32]) -> Self {
+impl VertexFormat for LightCube {
+    fn layout() -> VertexLayout {
+        VertexLayout::new(8 * F32_SIZE).attribute(Attribute::float(0, 3, 0))
+    }
+}
+
+/// Plain data that buffers receive byte for byte, such as vertices and instances.
+///
+/// # Safety
+/// Implementors are primitives or `#[repr(C)]` aggregates of them without
+/// padding, pointers or references, so that every byte is initialized data.
+pub unsafe trait Pod: Copy + 'static {}
+
+unsafe impl Pod for f32 {}
+unsafe impl Pod for i32 {}
+unsafe impl Pod for u32 {}
+unsafe impl Pod for i16 {}
+unsafe impl Pod for u16 {}
+unsafe impl Pod for i8 {}
+unsafe impl Pod for u8 {}
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+pub trait IndexType: Pod {
+    const TYPE: GLenum;
+}
+
+impl IndexType for u16 {
+    const TYPE: GLenum = gl::UNSIGNED_SHORT;
+}
+
+impl IndexType for u32 {
+    const TYPE: GLenum = gl::UNSIGNED_INT;
+}
+
+impl<T> Graphics<T> {
+    /// Uploads `vertices`, read through `layout`.
+    ///
+    /// # Panics
+    /// If the layout's stride is 0.
+    pub fn with_layout<V: Pod>(vertices: &[V], layout: &VertexLayout) -> Self {
+        assert!(layout.stride > 0, "vertex layouts need a non-zero stride");
+
         let (mut vbo, mut vao) = (0, 0);
 
         unsafe {
@@ -56,53 +197,193 @@ impl Graphics<Lighting> {
 
             gl::GenVertexArrays(1, &mut vao as _);
             gl::BindVertexArray(vao);
-            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, 6 * F32_SIZE, 0 as _);
-            gl::EnableVertexAttribArray(0);
-            gl::VertexAttribPointer(
-                1,
-                3,
-                gl::FLOAT,
-                gl::FALSE,
-                6 * F32_SIZE,
-                (3 * F32_SIZE) as _,
-            );
-            gl::EnableVertexAttribArray(1);
         }
 
+        layout.apply();
+
         Self {
             vbo,
             vao,
-            vertex_count: vertices.len() / 6,
+            vertex_count: size_of_val(vertices) / layout.stride,
+            elements: None,
+            instances: None,
             _t: Default::default(),
         }
     }
-}
 
-impl Graphics<LightCube> {
-    pub fn new(vertices: &[f32]) -> Self {
-        let (mut vbo, mut vao) = (0, 0);
+    /// Attaches an element buffer; `draw` then goes through `glDrawElements`.
+    pub fn with_indices<I: IndexType>(mut self, indices: &[I]) -> Self {
+        let mut ebo = 0;
 
         unsafe {
+            // the element buffer binding is recorded in the VAO
+            gl::BindVertexArray(self.vao);
+            gl::GenBuffers(1, &mut ebo as _);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER,
+                size_of_val(indices) as _,
+                indices.as_ptr() as _,
+                gl::STATIC_DRAW,
+            );
+        }
+
+        if let Some(mut old) = self.elements.replace(Elements {
+            ebo,
+            count: indices.len(),
+            kind: I::TYPE,
+        }) {
+            unsafe {
+                gl::DeleteBuffers(1, &mut old.ebo as _);
+            }
+        }
+
+        self
+    }
+
+    /// Adds a second buffer with per-instance attributes, filled by `set_instances`.
+    ///
+    /// # Panics
+    /// If the layout's stride is 0.
+    pub fn with_instances(mut self, layout: &VertexLayout) -> Self {
+        assert!(layout.stride > 0, "instance layouts need a non-zero stride");
+
+        let mut vbo = 0;
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
             gl::GenBuffers(1, &mut vbo as _);
             gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        }
+
+        layout.apply();
+
+        if let Some(mut old) = self.instances.replace(Instances {
+            vbo,
+            count: 0,
+            stride: layout.stride,
+        }) {
+            unsafe {
+                gl::DeleteBuffers(1, &mut old.vbo as _);
+            }
+        }
+
+        self
+    }
+
+    /// Replaces the instance data; meant to be called every frame.
+    ///
+    /// # Panics
+    /// If `with_instances` has not been called or `V` is not the size of its
+    /// layout's stride.
+    pub fn set_instances<V: Pod>(&mut self, instances: &[V]) {
+        let buffer = self
+            .instances
+            .as_mut()
+            .expect("set_instances requires with_instances");
+        assert_eq!(
+            size_of::<V>(),
+            buffer.stride,
+            "instances must match the stride of their layout"
+        );
+        buffer.count = instances.len();
+
+        unsafe {
+            gl::BindBuffer(gl::ARRAY_BUFFER, buffer.vbo);
             gl::BufferData(
                 gl::ARRAY_BUFFER,
-                size_of_val(vertices) as _,
-                vertices.as_ptr() as _,
-                gl::STATIC_DRAW,
+                size_of_val(instances) as _,
+                instances.as_ptr() as _,
+                gl::STREAM_DRAW,
             );
+        }
+    }
+
+    pub fn draw(&self) {
+        unsafe {
+            gl::BindVertexArray(self.vao);
+
+            match &self.elements {
+                Some(elements) => gl::DrawElements(
+                    gl::TRIANGLES,
+                    elements.count as _,
+                    elements.kind,
+                    ptr::null(),
+                ),
+                None => gl::DrawArrays(gl::TRIANGLES, 0, self.vertex_count as _),
+            }
+        }
+    }
+
+    /// Draws every instance from the last `set_instances` in one call.
+    pub fn draw_instanced(&self) {
+        let count = self.instances.as_ref().map_or(0, |i| i.count);
+
+        if count == 0 {
+            return;
+        }
+
+        unsafe {
+            gl::BindVertexArray(self.vao);
+
+            match &self.elements {
+                Some(elements) => gl::DrawElementsInstanced(
+                    gl::TRIANGLES,
+                    elements.count as _,
+                    elements.kind,
+                    ptr::null(),
+                    count as _,
+                ),
+                None => {
+                    gl::DrawArraysInstanced(gl::TRIANGLES, 0, self.vertex_count as _, count as _)
+                }
+            }
+        }
+    }
+}
 
+impl<T: VertexFormat> Graphics<T> {
+    pub fn new<V: Pod>(vertices: &[V]) -> Self {
+        Self::with_layout(vertices, &T::layout())
+    }
+}
+
+/// A single triangle covering the viewport, generated from `gl_VertexID` by
+/// `fullscreen.vs`. Core profiles still need a VAO bound to draw it.
+#[derive(Debug)]
+pub struct Fullscreen {
+    vao: GLuint,
+}
+
+impl Fullscreen {
+    pub fn new() -> Self {
+        let mut vao = 0;
+
+        unsafe {
             gl::GenVertexArrays(1, &mut vao as _);
-            gl::BindVertexArray(vao);
-            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, 6 * F32_SIZE, 0 as _);
-            gl::EnableVertexAttribArray(0);
         }
 
-        Self {
-            vbo,
-            vao,
-            vertex_count: vertices.len() / 6,
-            _t: Default::default(),
+        Self { vao }
+    }
+
+    pub fn draw(&self) {
+        unsafe {
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::TRIANGLES, 0, 3);
+        }
+    }
+}
+
+impl Default for Fullscreen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Fullscreen {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &mut self.vao as _);
         }
     }
 }
@@ -112,6 +393,14 @@ impl<T> Drop for Graphics<T> {
         unsafe {
             gl::DeleteVertexArrays(1, &mut self.vao as _);
             gl::DeleteBuffers(1, &mut self.vbo as _);
+
+            if let Some(elements) = &mut self.elements {
+                gl::DeleteBuffers(1, &mut elements.ebo as _);
+            }
+
+            if let Some(instances) = &mut self.instances {
+                gl::DeleteBuffers(1, &mut instances.vbo as _);
+            }
         }
     }
 }
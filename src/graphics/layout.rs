@@ -0,0 +1,116 @@
+use gl::types::{GLenum, GLint, GLuint};
+
+/// One vertex attribute inside a buffer, as passed to `glVertexAttribPointer`.
+#[derive(Debug, Clone, Copy)]
+pub struct Attribute {
+    pub location: GLuint,
+    pub components: GLint,
+    pub kind: GLenum,
+    pub normalized: bool,
+    /// Keeps integer data as integers in the shader (`glVertexAttribIPointer`),
+    /// e.g. for bone indices.
+    pub integer: bool,
+    /// Byte offset from the start of a vertex.
+    pub offset: usize,
+}
+
+impl Attribute {
+    pub const fn float(location: GLuint, components: GLint, offset: usize) -> Self {
+        Self {
+            location,
+            components,
+            kind: gl::FLOAT,
+            normalized: false,
+            integer: false,
+            offset,
+        }
+    }
+
+    /// Fixed-point data mapped to `[0, 1]` or `[-1, 1]`, e.g. 8-bit colors.
+    pub const fn normalized(
+        location: GLuint,
+        components: GLint,
+        kind: GLenum,
+        offset: usize,
+    ) -> Self {
+        Self {
+            location,
+            components,
+            kind,
+            normalized: true,
+            integer: false,
+            offset,
+        }
+    }
+
+    pub const fn integer(location: GLuint, components: GLint, kind: GLenum, offset: usize) -> Self {
+        Self {
+            location,
+            components,
+            kind,
+            normalized: false,
+            integer: true,
+            offset,
+        }
+    }
+}
+
+/// Describes how a single interleaved buffer feeds vertex attributes.
+#[derive(Debug, Clone)]
+pub struct VertexLayout {
+    /// Bytes between consecutive vertices.
+    pub stride: usize,
+    /// Advance once per this many instances instead of once per vertex; 0 disables.
+    pub divisor: GLuint,
+    pub attributes: Vec<Attribute>,
+}
+
+impl VertexLayout {
+    pub fn new(stride: usize) -> Self {
+        Self {
+            stride,
+            divisor: 0,
+            attributes: Vec::new(),
+        }
+    }
+
+    pub fn attribute(mut self, attribute: Attribute) -> Self {
+        self.attributes.push(attribute);
+        self
+    }
+
+    pub fn divisor(mut self, divisor: GLuint) -> Self {
+        self.divisor = divisor;
+        self
+    }
+
+    /// Points the attributes at the buffer bound to `GL_ARRAY_BUFFER`, recording
+    /// them in the currently bound VAO.
+    pub(super) fn apply(&self) {
+        for a in &self.attributes {
+            unsafe {
+                if a.integer {
+                    gl::VertexAttribIPointer(
+                        a.location,
+                        a.components,
+                        a.kind,
+                        self.stride as _,
+                        a.offset as _,
+                    );
+                } else {
+                    gl::VertexAttribPointer(
+                        a.location,
+                        a.components,
+                        a.kind,
+                        if a.normalized { gl::TRUE } else { gl::FALSE },
+                        self.stride as _,
+                        a.offset as _,
+                    );
+                }
+
+                gl::VertexAttribDivisor(a.location, self.divisor);
+                gl::EnableVertexAttribArray(a.location);
+            }
+        }
+    }
+}
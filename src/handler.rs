@@ -16,14 +16,32 @@ pub struct Handler {
 impl Handler {
     const FPS: u32 = 275;
     const INTERVAL: Duration = Duration::new(0, 1_000_000_000 / Self::FPS);
+    pub const FIXED_DELTA: f32 = 1. / Self::FPS as f32;
 
     pub fn new(title: &str, width: u32, height: u32) -> Result<Self> {
+        Self::init(title, width, height, false)
+    }
+
+    /// Creates a hidden window on SDL's `offscreen` video driver, which runs on
+    /// EGL without a display (e.g. Mesa llvmpipe via `EGL_PLATFORM=surfaceless`).
+    /// Draw into a [`crate::render_target::RenderTarget`] and read it back.
+    pub fn headless(width: u32, height: u32) -> Result<Self> {
+        sdl2::hint::set("SDL_VIDEODRIVER", "offscreen");
+        Self::init("Headless", width, height, true)
+    }
+
+    fn init(title: &str, width: u32, height: u32, hidden: bool) -> Result<Self> {
         let sdl_context = sdl2::init().map_err(|x| anyhow!(x))?;
 
         let video_subsystem = sdl_context.video().map_err(|x| anyhow!(x))?;
 
-        let canvas = video_subsystem
-            .window(title, width, height)
+        let mut window = video_subsystem.window(title, width, height);
+
+        if hidden {
+            window.hidden();
+        }
+
+        let canvas = window
             .opengl()
             .build()
             .map_err(|x| anyhow!(x))?
@@ -43,7 +61,9 @@ impl Handler {
             .gl_set_context_to_current()
             .expect("gl_set_context_to_current failed");
 
-        sdl_context.mouse().set_relative_mouse_mode(true);
+        if !hidden {
+            sdl_context.mouse().set_relative_mouse_mode(true);
+        }
 
         Ok(Self {
             sdl_context,
@@ -0,0 +1,25 @@
+// lets `#[derive(Uniforms)]` name this crate from inside it too
+extern crate self as three_d;
+
+pub mod asset;
+pub mod camera;
+pub mod capture;
+pub mod cluster;
+pub mod cube;
+pub mod deferred;
+pub mod graphics;
+pub mod handler;
+pub mod lights;
+pub mod material;
+pub mod mesh;
+pub mod model;
+pub mod options;
+pub mod particles;
+pub mod post;
+pub mod render_target;
+pub mod renderer;
+pub mod scene;
+pub mod shader;
+pub mod shadow;
+pub mod texture;
+pub mod util;
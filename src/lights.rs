@@ -0,0 +1,90 @@
+use crate::shader::{
+    preprocessor::Defines, shader_program::ShaderProgram, texture_buffer::TextureBuffer,
+};
+use anyhow::{anyhow, Result};
+use gl::types::GLuint;
+use glm::Vec3;
+
+/// Per-unit-distance falloff, `LIGHT_DECAY` in `lighting.fs`.
+pub const LIGHT_DECAY: f32 = 1.2;
+/// Strength below which `lighting.fs` ignores a light.
+pub const LIGHT_CUTOFF: f32 = 0.01;
+/// Per-unit-distance fade of surfaces into the fog color.
+pub const DST_DECAY: f32 = 1.075;
+/// Scales light colors to radiance in PBR shading, whose inverse-square falloff
+/// and energy-conserving diffuse are much darker than the classic model.
+pub const LIGHT_INTENSITY: f32 = 8.;
+
+/// These constants and [`radius`] (as `LIGHT_RANGE`) as GLSL defines.
+pub fn defines() -> Defines {
+    Defines::new()
+        .float("LIGHT_DECAY", LIGHT_DECAY)
+        .float("LIGHT_CUTOFF", LIGHT_CUTOFF)
+        .float("DST_DECAY", DST_DECAY)
+        .float("LIGHT_INTENSITY", LIGHT_INTENSITY)
+        .float("LIGHT_RANGE", radius())
+}
+
+/// Distance at which a light's `LIGHT_DECAY^-distance` falloff drops below
+/// [`LIGHT_CUTOFF`]; PBR shading fades lights out at the same distance, so
+/// clusters and light volumes fit both models.
+pub fn radius() -> f32 {
+    (1. / LIGHT_CUTOFF).ln() / LIGHT_DECAY.ln()
+}
+
+/// Every light of the scene in one `RGBA32F` buffer texture, read by
+/// `lighting.fs`: texel `2 * i` holds the position of light `i` and the next one
+/// its color.
+#[derive(Debug)]
+pub struct LightBuffer {
+    buffer: TextureBuffer,
+    texels: Vec<[f32; 4]>,
+    /// Lights addressable in the buffer texture, at least 32768.
+    capacity: usize,
+}
+
+impl LightBuffer {
+    const UNIT: GLuint = 7;
+
+    pub fn new() -> Self {
+        Self {
+            buffer: TextureBuffer::new(gl::RGBA32F),
+            texels: Vec::new(),
+            capacity: TextureBuffer::max_texels() / 2,
+        }
+    }
+
+    /// Uploads the lights; meant to be called every frame. Fails, uploading
+    /// nothing, if there are more than the buffer texture can address.
+    pub fn update(&mut self, positions: &[Vec3], colors: &[Vec3]) -> Result<()> {
+        if positions.len() > self.capacity {
+            Err(anyhow!(
+                "{} lights exceed the {} the light buffer can address",
+                positions.len(),
+                self.capacity
+            ))?
+        }
+
+        self.texels.clear();
+        self.texels.extend(
+            positions
+                .iter()
+                .zip(colors)
+                .flat_map(|(p, c)| [[p.x, p.y, p.z, 1.], [c.x, c.y, c.z, 1.]]),
+        );
+        self.buffer.upload(&self.texels);
+        Ok(())
+    }
+
+    /// Binds the buffer to the `lights` sampler of `program`.
+    pub fn bind(&self, program: &ShaderProgram) {
+        self.buffer.bind(Self::UNIT);
+        program.set_i32(c"lights", Self::UNIT as _);
+    }
+}
+
+impl Default for LightBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,67 +1,117 @@
-mod camera;
-mod cube;
-mod graphics;
-mod handler;
-mod shader;
-mod util;
-
-use camera::Camera;
-use cube::Cube;
 use glm::ext as gle;
-use glm::{Mat4, Vec3};
-use graphics::{Graphics, LightCube, Lighting};
-use handler::Handler;
-use num_traits::identities::One;
-use rand::distributions::Uniform;
-use rand::Rng;
+use glm::Mat4;
+use num_traits::One;
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
 use sdl2::mouse::MouseButton;
-use shader::shader_program::ShaderProgram;
-use std::array;
-use std::time::Instant;
+use std::fs;
+use std::time::{Duration, Instant};
+use three_d::{
+    asset,
+    capture::Capture,
+    handler::Handler,
+    options::Options,
+    post::color_grade::ColorGrade,
+    render_target::{Format, RenderTargetBuilder},
+    renderer::Renderer,
+    scene::{Scene, Sun},
+    shader::program_cache::ProgramCache,
+    texture::Texture,
+    util,
+};
+
+/// How often shader sources are checked for changes in windowed mode.
+const SHADER_POLL: Duration = Duration::from_millis(250);
 
 fn main() {
+    let options = Options::from_args().expect("could not parse arguments");
     let (width, height) = (1600, 900);
     let aspect_ratio = width as f32 / height as f32;
-    let mut handler = Handler::new("Window", width, height).expect("could not obtain handler");
-
-    let lighting = Graphics::<Lighting>::new(graphics::CUBE);
-    let light_cube = Graphics::<LightCube>::new(graphics::CUBE);
-
-    let shader_lighting =
-        ShaderProgram::new("lighting.vs", "lighting.fs").expect("could not obtain shader_lighting");
-    let shader_light_cube =
-        ShaderProgram::new("light_cube.vs", "light_cube.fs").expect("could not obtain light_cube");
-
-    let mut camera = Camera::new();
-    let mut cubes: [Cube; 256] = array::from_fn(|_| Cube::new());
-    const LIGHTS: usize = 225;
-    let light_positions: [Vec3; LIGHTS] = array::from_fn(|i| {
-        let i = i as i32;
-        let x = i / 15 - 7;
-        let z = i % 15 - 7;
-        glm::vec3((x * 12) as f32, 0., (z * 12) as f32)
+    let mut handler = match options.headless {
+        Some(_) => Handler::headless(width, height),
+        None => Handler::new("Window", width, height),
+    }
+    .expect("could not obtain handler");
+
+    let offscreen = options.headless.as_ref().map(|headless| {
+        fs::create_dir_all(&headless.output).expect("could not create output directory");
+        RenderTargetBuilder::new(width, height)
+            .color(Format::Rgba8)
+            .depth(Format::Depth24)
+            .build()
+            .expect("could not obtain offscreen render target")
     });
-    let light_colors: [Vec3; LIGHTS] = {
-        let mut rng = rand::thread_rng();
-        let uniform = Uniform::new(0., glm::radians(360.));
-
-        array::from_fn(|_| {
-            let theta = rng.sample(uniform);
-            let c = |offset: f32| 0.75 + 0.25 * (theta + glm::radians(offset)).sin();
-            glm::vec3(c(0.), c(120.), c(240.))
-        })
-    };
-
-    let floor = Cube::floor();
-    let object_color = glm::vec3(1., 1., 1.);
-    let fog_color = glm::vec3(0.2, 0.25, 0.3);
-    let ambient_color = fog_color * 0.2;
-    let projection = gle::perspective(glm::radians(90.), aspect_ratio, 0.1, 100.);
+
+    if let Some(dir) = &options.shader_cache {
+        if let Err(e) = ProgramCache::enable(dir) {
+            eprintln!("shader cache disabled: {e:#}");
+        }
+    }
+
+    let mut renderer = Renderer::new(options.pipeline, options.samples, width, height)
+        .expect("could not obtain renderer");
+    let mut scene = Scene::new(aspect_ratio);
+    scene.set_shading(options.shading);
+
+    if let Err(e) = renderer.set_shadowed_lights(options.shadows) {
+        eprintln!("shadows disabled: {e:#}");
+    }
+
+    if let Err(e) = renderer.set_particles(options.particles) {
+        eprintln!("particles disabled: {e:#}");
+    }
+
+    renderer.set_show_normals(options.normals);
+    renderer.set_tone_mapper(options.tone_mapper);
+    renderer.set_exposure(options.exposure);
+
+    if let Some(path) = &options.lut {
+        let lut = Texture::lut(path).expect("could not load lookup table");
+        let post = renderer.post_mut();
+        post.effect_mut::<ColorGrade>()
+            .expect("the post stack grades")
+            .set_lut(lut);
+        post.set_enabled("grade", true);
+    }
+
+    if let Some(names) = &options.post {
+        let post = renderer.post_mut();
+        let known: Vec<_> = post.names().collect();
+
+        if let Some(unknown) = names.iter().find(|name| !known.contains(&name.as_str())) {
+            panic!("unknown post-processing pass {unknown}, expected one of {known:?}");
+        }
+
+        for name in known {
+            post.set_enabled(name, name == "tonemap" || names.iter().any(|n| n == name));
+        }
+    }
+
+    if options.sun {
+        scene.sun = Some(Sun::default());
+    }
+
+    if let Some(cubes) = options.cubes {
+        scene.resize_cubes(cubes);
+    }
+
+    // assets are Y-up while the scene is Y-down, so flip them and stand them on the floor
+    let placement = gle::scale(
+        &gle::translate(&Mat4::one(), glm::vec3(0., 0.5, 0.)),
+        glm::vec3(1., -1., 1.),
+    );
+
+    for path in &options.models {
+        let models = asset::load_models(path, placement).expect("could not load model");
+        scene.models.extend(models);
+    }
 
     let instant = Instant::now();
     let mut event_pump = handler.event_pump().expect("failed to obtain event_pump");
+    let mut frame = 0;
+    let mut shader_poll = Instant::now();
+    let mut capture = Capture::new(&options.captures);
+    let mut screenshot = false;
 
     'main_loop: loop {
         for event in event_pump.poll_iter() {
@@ -71,17 +121,75 @@ fn main() {
                     keycode: Some(Keycode::Escape),
                     ..
                 } => break 'main_loop,
+                Event::KeyDown {
+                    keycode: Some(Keycode::T),
+                    repeat: false,
+                    ..
+                } => {
+                    renderer.set_tone_mapper(renderer.tone_mapper().next());
+                    println!("tone mapper: {:?}", renderer.tone_mapper());
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F12),
+                    repeat: false,
+                    ..
+                } => screenshot = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::R),
+                    repeat: false,
+                    ..
+                } => match capture.stop_recording() {
+                    Some(frames) => println!("recording stopped after {frames} frames"),
+                    None => {
+                        let t = instant.elapsed().as_secs_f32();
+                        match capture.start_recording(t) {
+                            Ok(dir) => println!("recording into {}", dir.display()),
+                            Err(e) => eprintln!("could not start recording: {e:#}"),
+                        }
+                    }
+                },
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } => {
+                    let pass = match keycode {
+                        Keycode::B => "bloom",
+                        Keycode::G => "grade",
+                        Keycode::V => "vignette",
+                        Keycode::F => "fxaa",
+                        _ => continue,
+                    };
+
+                    if let Some(enabled) = renderer.post_mut().toggle(pass) {
+                        println!("{pass}: {}", if enabled { "on" } else { "off" });
+                    }
+                }
                 Event::MouseMotion { xrel, yrel, .. } => {
-                    camera.input_mouse(xrel, yrel);
+                    scene.camera.input_mouse(xrel, yrel);
                 }
                 _ => {}
             }
         }
 
-        let dt = handler.delta().as_secs_f32();
-        let t = instant.elapsed().as_secs_f32();
-        camera.input_kb(event_pump.keyboard_state(), dt);
-        let force_mult = {
+        // headless runs and recordings are driven by a fixed timestep so that
+        // output is reproducible and plays back at its own rate
+        let (dt, t) = match (&options.headless, capture.timestep()) {
+            (Some(_), _) => (Handler::FIXED_DELTA, frame as f32 * Handler::FIXED_DELTA),
+            (None, Some(timestep)) => {
+                // so the first delta after recording doesn't span all of it
+                handler.delta();
+                timestep
+            }
+            (None, None) => (
+                handler.delta().as_secs_f32(),
+                instant.elapsed().as_secs_f32(),
+            ),
+        };
+        let force_mult = if options.headless.is_some() {
+            0.
+        } else {
+            scene.camera.input_kb(event_pump.keyboard_state(), dt);
             let ms = event_pump.mouse_state();
             if ms.is_mouse_button_pressed(MouseButton::Left) {
                 1.
@@ -92,42 +200,57 @@ fn main() {
             }
         } * 1000.;
 
-        unsafe {
-            gl::ClearColor(fog_color.x, fog_color.y, fog_color.z, 1.);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        scene.update(dt, force_mult);
+
+        if options.headless.is_none() && shader_poll.elapsed() >= SHADER_POLL {
+            renderer.reload_shaders();
+            shader_poll = Instant::now();
         }
 
-        shader_lighting.enable();
-        shader_lighting.set_f32(c"time", t);
-        shader_lighting.set_u32(c"lights", LIGHTS as _);
-        shader_lighting.set_vec3(c"objectColor", object_color);
-        shader_lighting.set_vec3(c"fogColor", fog_color);
-        shader_lighting.set_vec3(c"ambientColor", ambient_color);
-        shader_lighting.set_vec3(c"viewPos", camera.position);
-        shader_lighting.set_vec3_array(c"lightPositions", &light_positions);
-        shader_lighting.set_vec3_array(c"lightColors", &light_colors);
-        shader_lighting.set_mat4(c"projection", &projection);
-        shader_lighting.set_mat4(c"view", &camera.view());
-        floor.draw(&lighting, &shader_lighting);
-
-        for cube in &mut cubes {
-            let diff = camera.position + camera.front() * 12. - cube.position;
-            cube.update(dt, (glm::normalize(diff) / glm::length(diff)) * force_mult);
-            cube.draw(&lighting, &shader_lighting);
+        if let Some(offscreen) = &offscreen {
+            offscreen.bind();
         }
 
-        shader_light_cube.enable();
-        shader_light_cube.set_mat4(c"view", &camera.view());
-        shader_light_cube.set_mat4(c"projection", &projection);
+        renderer.draw(&scene, t).expect("could not draw frame");
 
-        for (pos, color) in light_positions.into_iter().zip(light_colors) {
-            let model = gle::scale(&gle::translate(&Mat4::one(), pos), Vec3::one() * 0.1);
-            shader_light_cube.set_vec3(c"lightColor", color);
-            shader_light_cube.set_mat4(c"model", &model);
-            light_cube.draw();
+        if options.check_uniforms {
+            renderer.check_uniforms();
         }
 
-        handler.present();
-        handler.sleep();
+        if let (Some(headless), Some(offscreen)) = (&options.headless, &offscreen) {
+            if headless.should_dump(frame) {
+                let path = headless.output.join(format!("frame_{frame:05}.png"));
+                util::save_png(
+                    &path,
+                    offscreen.width(),
+                    offscreen.height(),
+                    &offscreen.read_pixels().expect("could not read back frame"),
+                )
+                .expect("could not save frame");
+            }
+
+            frame += 1;
+
+            if frame >= headless.frames {
+                break 'main_loop;
+            }
+        } else {
+            if screenshot {
+                match capture.screenshot(width, height) {
+                    Ok(path) => println!("saved {}", path.display()),
+                    Err(e) => eprintln!("could not save screenshot: {e:#}"),
+                }
+
+                screenshot = false;
+            }
+
+            if let Err(e) = capture.record(width, height) {
+                capture.stop_recording();
+                eprintln!("could not record frame, recording stopped: {e:#}");
+            }
+
+            handler.present();
+            handler.sleep();
+        }
     }
 }
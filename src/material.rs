@@ -0,0 +1,105 @@
+//! Surface parameters of lit geometry. Factors travel per instance, so objects
+//! drawn in one call may all look different; texture maps are bound per draw.
+
+use crate::{model::PbrFactors, shader::shader_program::ShaderProgram, texture::Texture};
+use glm::Vec3;
+use num_traits::Zero;
+use std::rc::Rc;
+
+/// The reflection model of a material, see `include/shading.glsl`. Both run in
+/// the same programs, so materials of either kind are drawn together.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Shading {
+    /// Phong with the material's specular strength and shininess.
+    #[default]
+    Classic,
+    /// Energy-conserving Cook-Torrance GGX with the material's metallic and
+    /// roughness.
+    Pbr,
+}
+
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub shading: Shading,
+    pub albedo: Vec3,
+    /// Strength of the specular highlight.
+    pub specular: f32,
+    /// Phong exponent; higher is a smaller, sharper highlight.
+    pub shininess: f32,
+    /// Share of the surface that is metal, for PBR shading.
+    pub metallic: f32,
+    /// Perceptual roughness from mirror-like 0 to fully rough 1, for PBR shading.
+    pub roughness: f32,
+    /// Light given off regardless of lighting, not subject to shadows.
+    pub emissive: Vec3,
+    /// Repetitions of the maps per unit of texture coordinates.
+    pub tiling: f32,
+    pub maps: MaterialMaps,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            shading: Shading::Classic,
+            albedo: glm::vec3(1., 1., 1.),
+            specular: 0.5,
+            shininess: 32.,
+            metallic: 0.,
+            roughness: 0.5,
+            emissive: Vec3::zero(),
+            tiling: 1.,
+            maps: MaterialMaps::default(),
+        }
+    }
+}
+
+impl Material {
+    /// PBR shading with glTF metallic-roughness factors, also approximated for
+    /// classic shading: rough surfaces get broad, weak highlights.
+    pub fn from_pbr(factors: &PbrFactors) -> Self {
+        let color = factors.base_color;
+        let smoothness = 1. - factors.roughness.clamp(0., 1.);
+
+        Self {
+            shading: Shading::Pbr,
+            albedo: glm::vec3(color.x, color.y, color.z),
+            specular: 0.04 + 0.96 * smoothness * smoothness,
+            shininess: 2. + 254. * smoothness.powi(4),
+            metallic: factors.metallic.clamp(0., 1.),
+            roughness: factors.roughness.clamp(0., 1.),
+            emissive: factors.emissive,
+            ..Default::default()
+        }
+    }
+}
+
+/// Optional maps multiplying the albedo and the specular strength. Materials
+/// sharing the same textures can be drawn together.
+#[derive(Debug, Clone, Default)]
+pub struct MaterialMaps {
+    /// sRGB color, sampled as `diffuseMap`.
+    pub diffuse: Option<Rc<Texture>>,
+    /// Linear, sampled as `specularMap`. Classic shading scales the specular
+    /// strength by red; PBR shading reads it as a glTF metallic-roughness map,
+    /// scaling roughness by green and metallic by blue.
+    pub specular: Option<Rc<Texture>>,
+}
+
+impl MaterialMaps {
+    /// Whether both refer to the same textures.
+    pub fn same(&self, other: &Self) -> bool {
+        let same = |a: &Option<Rc<Texture>>, b: &Option<Rc<Texture>>| match (a, b) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        };
+
+        same(&self.diffuse, &other.diffuse) && same(&self.specular, &other.specular)
+    }
+
+    /// Sets both samplers of `program`; missing maps are replaced by `white`.
+    pub fn bind(&self, program: &ShaderProgram, white: &Texture) {
+        program.set_texture(c"diffuseMap", self.diffuse.as_deref().unwrap_or(white));
+        program.set_texture(c"specularMap", self.specular.as_deref().unwrap_or(white));
+    }
+}
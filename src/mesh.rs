@@ -0,0 +1,124 @@
+use crate::graphics::{Graphics, Lighting};
+use glm::{Vec2, Vec3};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vertex {
+    pub position: Vec3,
+    pub normal: Vec3,
+    pub tex_coord: Vec2,
+}
+
+impl Vertex {
+    /// Bitwise identity, so that vertices can be hashed.
+    fn key(&self) -> [u32; 8] {
+        let (p, n, t) = (self.position, self.normal, self.tex_coord);
+        [p.x, p.y, p.z, n.x, n.y, n.z, t.x, t.y].map(f32::to_bits)
+    }
+}
+
+/// Triangle list on the CPU side, three vertices per triangle.
+#[derive(Debug, Default, Clone)]
+pub struct Mesh {
+    pub vertices: Vec<Vertex>,
+}
+
+/// Unique vertices plus a triangle list of indices into them.
+#[derive(Debug, Default, Clone)]
+pub struct IndexedMesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Merges bitwise identical vertices.
+    pub fn deduplicate(&self) -> IndexedMesh {
+        let mut mesh = IndexedMesh::default();
+        let mut seen = HashMap::new();
+
+        for vertex in &self.vertices {
+            let index = *seen.entry(vertex.key()).or_insert_with(|| {
+                mesh.vertices.push(*vertex);
+                mesh.vertices.len() as u32 - 1
+            });
+            mesh.indices.push(index);
+        }
+
+        mesh
+    }
+}
+
+impl IndexedMesh {
+    /// Uploads the mesh, with 16-bit indices whenever the vertex count allows.
+    pub fn graphics(&self) -> Graphics<Lighting> {
+        let graphics = Graphics::<Lighting>::new(&lighting_vertices(&self.vertices));
+
+        if self.vertices.len() <= u16::MAX as usize + 1 {
+            let indices: Vec<u16> = self.indices.iter().map(|&i| i as _).collect();
+            graphics.with_indices(&indices)
+        } else {
+            graphics.with_indices(&self.indices)
+        }
+    }
+}
+
+/// Interleaves positions, normals and texture coordinates in the layout of
+/// [`crate::graphics::CUBE_VERTICES`].
+fn lighting_vertices(vertices: &[Vertex]) -> Vec<f32> {
+    vertices
+        .iter()
+        .flat_map(|v| {
+            let (p, n, t) = (v.position, v.normal, v.tex_coord);
+            [p.x, p.y, p.z, n.x, n.y, n.z, t.x, t.y]
+        })
+        .collect()
+}
+
+/// Normalizes `v`, pointing degenerate (zero-length) normals along +Y, the up axis
+/// of the asset formats.
+pub fn normalize_or_up(v: Vec3) -> Vec3 {
+    if glm::length(v) > f32::EPSILON {
+        glm::normalize(v)
+    } else {
+        glm::vec3(0., 1., 0.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{CUBE_INDICES, CUBE_VERTICES};
+
+    #[test]
+    fn deduplicate_collapses_the_unindexed_cube() {
+        let corners: Vec<_> = CUBE_VERTICES
+            .chunks_exact(8)
+            .map(|v| Vertex {
+                position: glm::vec3(v[0], v[1], v[2]),
+                normal: glm::vec3(v[3], v[4], v[5]),
+                tex_coord: glm::vec2(v[6], v[7]),
+            })
+            .collect();
+        let mesh = Mesh {
+            vertices: CUBE_INDICES.iter().map(|&i| corners[i as usize]).collect(),
+        };
+        assert_eq!(mesh.vertices.len(), 36);
+
+        let indexed = mesh.deduplicate();
+
+        // the 8 corner positions are not shared between faces, whose normals differ
+        assert_eq!(indexed.vertices.len(), 24);
+        // numbered in order of first use
+        assert_eq!(
+            indexed.indices,
+            [
+                0, 1, 2, 1, 0, 3, 4, 5, 6, 6, 7, 4, 8, 9, 10, 10, 11, 8, 12, 13, 14, 13, 12, 15,
+                16, 17, 18, 18, 19, 16, 20, 21, 22, 21, 20, 23,
+            ]
+        );
+
+        for (&index, vertex) in indexed.indices.iter().zip(&mesh.vertices) {
+            assert_eq!(indexed.vertices[index as usize], *vertex);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{
+    graphics::{Graphics, Instance, Lighting},
+    material::Material,
+    mesh::IndexedMesh,
+};
+use glm::{Mat4, Vec3, Vec4};
+
+/// Metallic-roughness material factors as found in glTF, approximated by
+/// [`Material::from_pbr`] for the lighting shader.
+#[derive(Debug, Clone, Copy)]
+pub struct PbrFactors {
+    pub base_color: Vec4,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: Vec3,
+}
+
+impl Default for PbrFactors {
+    fn default() -> Self {
+        Self {
+            base_color: glm::vec4(1., 1., 1., 1.),
+            metallic: 1.,
+            roughness: 1.,
+            emissive: glm::vec3(0., 0., 0.),
+        }
+    }
+}
+
+/// Static geometry drawn with the lighting shader as a single instance.
+#[derive(Debug)]
+pub struct Model {
+    graphics: Graphics<Lighting>,
+    transform: Mat4,
+    factors: PbrFactors,
+    material: Material,
+}
+
+impl Model {
+    pub fn new(mesh: &IndexedMesh, transform: Mat4, factors: PbrFactors) -> Self {
+        let mut model = Self {
+            graphics: mesh.graphics().with_instances(&Instance::layout()),
+            transform,
+            factors,
+            material: Material::from_pbr(&factors),
+        };
+        model.upload();
+        model
+    }
+
+    pub fn transform(&self) -> Mat4 {
+        self.transform
+    }
+
+    pub fn set_transform(&mut self, transform: Mat4) {
+        self.transform = transform;
+        self.upload();
+    }
+
+    pub fn factors(&self) -> &PbrFactors {
+        &self.factors
+    }
+
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Replaces the material derived from the factors.
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+        self.upload();
+    }
+
+    /// Draws with the current program; bind the material's maps first.
+    pub fn draw(&self) {
+        self.graphics.draw_instanced();
+    }
+
+    fn upload(&mut self) {
+        self.graphics
+            .set_instances(&[Instance::with_material(&self.transform, &self.material)]);
+    }
+}
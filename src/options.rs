@@ -0,0 +1,156 @@
+use crate::{material::Shading, post::tonemap::ToneMapper, renderer::Pipeline};
+use anyhow::{anyhow, Result};
+use std::{env, path::PathBuf};
+
+#[derive(Debug)]
+pub struct Options {
+    pub headless: Option<Headless>,
+    pub models: Vec<PathBuf>,
+    pub cubes: Option<usize>,
+    pub pipeline: Pipeline,
+    /// Of the cubes and the floor, see `Scene::set_shading`.
+    pub shading: Shading,
+    /// Samples per pixel of the scene, 1 without multisampling.
+    pub samples: u32,
+    pub tone_mapper: ToneMapper,
+    pub exposure: f32,
+    /// Post-processing passes to enable instead of the defaults, by name.
+    pub post: Option<Vec<String>>,
+    /// Lookup table graded through, see `Texture::lut`.
+    pub lut: Option<PathBuf>,
+    /// Point lights casting shadows, none by default.
+    pub shadows: usize,
+    /// Compute-simulated sparks, none by default, see `Particles`.
+    pub particles: usize,
+    pub sun: bool,
+    pub normals: bool,
+    pub check_uniforms: bool,
+    /// Where linked programs are cached, see `ProgramCache`.
+    pub shader_cache: Option<PathBuf>,
+    /// Where screenshots and recordings are written, see `Capture`.
+    pub captures: PathBuf,
+}
+
+/// Renders into an offscreen framebuffer instead of a visible window and
+/// writes frames to `output` as PNG.
+#[derive(Debug)]
+pub struct Headless {
+    pub frames: u32,
+    pub only: Option<u32>,
+    pub output: PathBuf,
+}
+
+impl Headless {
+    pub fn should_dump(&self, frame: u32) -> bool {
+        self.only.is_none_or(|only| only == frame)
+    }
+}
+
+impl Default for Headless {
+    fn default() -> Self {
+        Self {
+            frames: 1,
+            only: None,
+            output: PathBuf::from("frames"),
+        }
+    }
+}
+
+impl Options {
+    const USAGE: &str =
+        "usage: three_d [--model <path>]... [--cubes <count>] [--deferred] [--pbr] [--msaa <samples>] \
+        [--tonemap <reinhard|aces|exposure>] [--exposure <value>] \
+        [--post <name>,...] [--lut <path>] \
+        [--shadows <count>] [--particles <count>] [--sun] [--normals] [--check-uniforms] \
+        [--shader-cache <dir> | --no-shader-cache] [--captures <dir>] \
+        [--headless [--frames <count>] [--only <frame>] [--output <dir>]]";
+
+    pub fn from_args() -> Result<Self> {
+        let mut is_headless = false;
+        let mut headless = Headless::default();
+        let mut models = Vec::new();
+        let mut cubes = None;
+        let mut pipeline = Pipeline::Forward;
+        let mut shading = Shading::Classic;
+        let mut samples = 1;
+        let mut tone_mapper = ToneMapper::default();
+        let mut exposure = 1.;
+        let mut post = None;
+        let mut lut = None;
+        let mut shadows = 0;
+        let mut particles = 0;
+        let mut sun = false;
+        let mut normals = false;
+        let mut check_uniforms = false;
+        let mut shader_cache = Some(PathBuf::from("target/shader-cache"));
+        let mut captures = PathBuf::from("captures");
+        let mut args = env::args().skip(1);
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or(anyhow!("{arg} expects a value\n{}", Self::USAGE))
+            };
+
+            match arg.as_str() {
+                "--headless" => is_headless = true,
+                "--frames" => headless.frames = value()?.parse()?,
+                "--only" => headless.only = Some(value()?.parse()?),
+                "--output" => headless.output = value()?.into(),
+                "--model" => models.push(value()?.into()),
+                "--cubes" => cubes = Some(value()?.parse()?),
+                "--deferred" => pipeline = Pipeline::Deferred,
+                "--pbr" => shading = Shading::Pbr,
+                "--msaa" => samples = value()?.parse()?,
+                "--tonemap" => tone_mapper = value()?.parse()?,
+                "--exposure" => exposure = value()?.parse()?,
+                "--post" => post = Some(value()?.split(',').map(String::from).collect()),
+                "--lut" => lut = Some(value()?.into()),
+                "--shadows" => shadows = value()?.parse()?,
+                "--particles" => particles = value()?.parse()?,
+                "--sun" => sun = true,
+                "--normals" => normals = true,
+                "--check-uniforms" => check_uniforms = true,
+                "--shader-cache" => shader_cache = Some(value()?.into()),
+                "--no-shader-cache" => shader_cache = None,
+                "--captures" => captures = value()?.into(),
+                _ => Err(anyhow!("unknown argument {arg}\n{}", Self::USAGE))?,
+            }
+        }
+
+        if headless.frames == 0 {
+            Err(anyhow!("--frames must be at least 1\n{}", Self::USAGE))?
+        }
+
+        if let Some(only) = headless.only {
+            let Some(count) = only.checked_add(1) else {
+                return Err(anyhow!(
+                    "--only must be below {}\n{}",
+                    u32::MAX,
+                    Self::USAGE
+                ));
+            };
+            headless.frames = headless.frames.max(count);
+        }
+
+        Ok(Self {
+            headless: is_headless.then_some(headless),
+            models,
+            cubes,
+            pipeline,
+            shading,
+            samples,
+            tone_mapper,
+            exposure,
+            post,
+            lut,
+            shadows,
+            particles,
+            sun,
+            normals,
+            check_uniforms,
+            shader_cache,
+            captures,
+        })
+    }
+}
@@ -0,0 +1,171 @@
+use crate::{
+    graphics::Pod,
+    renderer::CameraUniforms,
+    scene::Scene,
+    shader::{
+        compute_program::ComputeProgram, preprocessor::Defines, shader_program::ShaderProgram,
+        storage_buffer::StorageBuffer, uniforms::Uniforms,
+    },
+};
+use anyhow::Result;
+use gl::types::GLuint;
+use glm::Vec3;
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Mirrors `Particle` in `include/particle.glsl`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+struct Particle {
+    /// `w` is the age in seconds, negative until the first spawn.
+    position: [f32; 4],
+    /// `w` is the lifetime in seconds.
+    velocity: [f32; 4],
+}
+
+// all fields are f32, so there is no padding
+unsafe impl Pod for Particle {}
+
+#[derive(Uniforms)]
+struct UpdateUniforms {
+    dt: f32,
+    time: f32,
+    emitter: Vec3,
+}
+
+#[derive(Uniforms)]
+struct DrawUniforms {
+    #[uniform(flatten)]
+    camera: CameraUniforms,
+    intensity: f32,
+}
+
+/// Sparks sprayed from the middle of the floor, simulated by `particles.cs` in
+/// a storage buffer and drawn as points straight from it. Needs OpenGL 4.3, see
+/// [`ComputeProgram::supported`].
+#[derive(Debug)]
+pub struct Particles {
+    particles: StorageBuffer<Particle>,
+    shader_update: ComputeProgram,
+    shader_draw: ShaderProgram,
+    vao: GLuint,
+    /// Scene time of the last update.
+    time: Option<f32>,
+}
+
+impl Particles {
+    /// Invocations per work group, `local_size_x` of `particles.cs`.
+    const GROUP_SIZE: u32 = 256;
+    /// Storage block binding shared by both programs.
+    const BINDING: GLuint = 0;
+    /// The same as the cubes'.
+    const GRAVITY: f32 = 10.;
+    const MAX_LIFETIME: f32 = 3.;
+    /// Scales the spark colors, bright enough in HDR to bloom.
+    const INTENSITY: f32 = 3.;
+
+    /// Fails unless [`ComputeProgram::supported`].
+    pub fn new(count: usize) -> Result<Self> {
+        let mut rng = StdRng::seed_from_u64(0);
+        let uni_lifetime = Uniform::new(Self::MAX_LIFETIME * 0.5, Self::MAX_LIFETIME);
+        // first spawns are staggered over a lifetime, so the fountain starts evenly
+        let particles: Vec<_> = (0..count)
+            .map(|_| {
+                let lifetime: f32 = rng.sample(uni_lifetime);
+                let delay: f32 = rng.gen_range(0. ..lifetime);
+                Particle {
+                    position: [0., 0., 0., -delay],
+                    velocity: [0., 0., 0., lifetime],
+                }
+            })
+            .collect();
+
+        let defines = Defines::new()
+            .define("GROUP_SIZE", Self::GROUP_SIZE)
+            .define("PARTICLE_BINDING", Self::BINDING)
+            .float("GRAVITY", Self::GRAVITY);
+        let shader_update = ComputeProgram::with_defines("particles.cs", &defines)?;
+        let shader_draw = ShaderProgram::with_defines("particles.vs", "particles.fs", &defines)?;
+        let particles = StorageBuffer::new(&particles)?;
+        let mut vao = 0;
+
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao as _);
+        }
+
+        Ok(Self {
+            particles,
+            shader_update,
+            shader_draw,
+            vao,
+            time: None,
+        })
+    }
+
+    /// Advances the simulation to scene time `t`.
+    pub fn update(&mut self, t: f32) {
+        let dt = self.time.map_or(0., |last| (t - last).max(0.));
+        self.time = Some(t);
+
+        self.particles.bind(Self::BINDING);
+        let program = self.shader_update.program();
+        program.enable();
+        UpdateUniforms {
+            dt,
+            time: t,
+            emitter: glm::vec3(0., -1., 0.),
+        }
+        .apply(program);
+
+        let groups = (self.particles.len() as u32).div_ceil(Self::GROUP_SIZE);
+        self.shader_update.dispatch(glm::Vector3::new(groups, 1, 1));
+        self.shader_update.barrier(gl::SHADER_STORAGE_BARRIER_BIT);
+    }
+
+    /// Adds the sparks to the bound framebuffer, depth tested but not written.
+    pub fn draw(&self, scene: &Scene) {
+        self.particles.bind(Self::BINDING);
+        let shader_draw = &self.shader_draw;
+        shader_draw.enable();
+        DrawUniforms {
+            camera: CameraUniforms::new(scene),
+            intensity: Self::INTENSITY,
+        }
+        .apply(shader_draw);
+
+        unsafe {
+            gl::Enable(gl::PROGRAM_POINT_SIZE);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::ONE, gl::ONE);
+            gl::DepthMask(gl::FALSE);
+
+            gl::BindVertexArray(self.vao);
+            gl::DrawArrays(gl::POINTS, 0, self.particles.len() as _);
+
+            gl::DepthMask(gl::TRUE);
+            gl::Disable(gl::BLEND);
+            gl::Disable(gl::PROGRAM_POINT_SIZE);
+        }
+    }
+
+    /// See [`ShaderProgram::reload`].
+    pub fn reload_shaders(&mut self) {
+        self.shader_update.reload();
+        self.shader_draw.reload();
+    }
+
+    /// See [`ShaderProgram::check_uniforms`].
+    pub fn check_uniforms(&self) {
+        self.shader_update.check_uniforms();
+        self.shader_draw.check_uniforms();
+    }
+}
+
+impl Drop for Particles {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteVertexArrays(1, &mut self.vao as _);
+        }
+    }
+}
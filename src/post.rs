@@ -0,0 +1,244 @@
+//! Full-screen passes between drawing the scene and presenting it. The scene is
+//! drawn into an HDR target, then every enabled [`Effect`] reads the previous
+//! result and writes the next, the last one into the framebuffer bound at
+//! [`PostStack::begin`].
+
+pub mod bloom;
+pub mod color_grade;
+pub mod fxaa;
+pub mod tonemap;
+pub mod vignette;
+
+use crate::{
+    graphics::Fullscreen,
+    render_target::{Format, Framebuffer, RenderTarget, RenderTargetBuilder},
+};
+use anyhow::Result;
+use std::any::Any;
+
+/// Color precision a pass writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    /// RGBA16F, for linear scene colors before tone mapping.
+    Hdr,
+    /// RGBA8, for display colors.
+    Ldr,
+}
+
+impl Precision {
+    fn format(self) -> Format {
+        match self {
+            Self::Hdr => Format::Rgba16F,
+            Self::Ldr => Format::Rgba8,
+        }
+    }
+}
+
+/// One full-screen pass of a [`PostStack`].
+pub trait Effect: Any {
+    /// Name used to toggle the pass.
+    fn name(&self) -> &'static str;
+
+    /// Precision of the color written.
+    fn output(&self) -> Precision;
+
+    /// Draws into the bound framebuffer and viewport, reading the previous result
+    /// from the first color attachment of `input` (see
+    /// [`RenderTarget::bind_color`]), single-sampled, linearly filtered and of
+    /// the viewport's size.
+    fn apply(&mut self, input: &RenderTarget, fullscreen: &Fullscreen);
+
+    /// See [`crate::shader::shader_program::ShaderProgram::reload`].
+    fn reload_shaders(&mut self);
+
+    /// See [`crate::shader::shader_program::ShaderProgram::check_uniforms`].
+    fn check_uniforms(&self);
+
+    /// Reallocates any targets of the pass for a new stack size.
+    fn resize(&mut self, _width: u32, _height: u32) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A linearly filtered color target passes draw into.
+fn color_target(width: u32, height: u32, precision: Precision) -> Result<RenderTarget> {
+    RenderTargetBuilder::new(width, height)
+        .color(precision.format())
+        .build()
+}
+
+struct Pass {
+    effect: Box<dyn Effect>,
+    enabled: bool,
+}
+
+pub struct PostStack {
+    passes: Vec<Pass>,
+    /// The scene is drawn into the first target, or into `multisampled` and
+    /// resolved into it; the HDR and LDR pairs are what passes alternate between.
+    targets: [RenderTarget; 4],
+    multisampled: Option<RenderTarget>,
+    fullscreen: Fullscreen,
+    /// Bound at `begin`, drawn into by the last pass.
+    output: Framebuffer,
+}
+
+impl PostStack {
+    /// An empty stack; `width` and `height` must match the framebuffer drawn into.
+    /// The scene is drawn with `samples` per pixel.
+    pub fn new(width: u32, height: u32, samples: u32) -> Result<Self> {
+        let scene = |samples| {
+            RenderTargetBuilder::new(width, height)
+                .color(Precision::Hdr.format())
+                .depth(Format::Depth24)
+                .samples(samples)
+                .build()
+        };
+
+        Ok(Self {
+            passes: Vec::new(),
+            targets: [
+                scene(1)?,
+                color_target(width, height, Precision::Hdr)?,
+                color_target(width, height, Precision::Ldr)?,
+                color_target(width, height, Precision::Ldr)?,
+            ],
+            multisampled: match samples {
+                0 | 1 => None,
+                _ => Some(scene(samples)?),
+            },
+            fullscreen: Fullscreen::new(),
+            output: Framebuffer::default(),
+        })
+    }
+
+    /// Appends `effect`, enabled or not; passes run in the order added.
+    pub fn with_effect(mut self, effect: impl Effect, enabled: bool) -> Self {
+        self.passes.push(Pass {
+            effect: Box::new(effect),
+            enabled,
+        });
+        self
+    }
+
+    /// The first pass of type `E`, for changing its settings.
+    pub fn effect_mut<E: Effect>(&mut self) -> Option<&mut E> {
+        self.passes.iter_mut().find_map(|pass| {
+            let effect: &mut dyn Any = pass.effect.as_mut();
+            effect.downcast_mut()
+        })
+    }
+
+    pub fn effect<E: Effect>(&self) -> Option<&E> {
+        self.passes.iter().find_map(|pass| {
+            let effect: &dyn Any = pass.effect.as_ref();
+            effect.downcast_ref()
+        })
+    }
+
+    /// Enables or disables the pass called `name`; false if there is none.
+    pub fn set_enabled(&mut self, name: &str, enabled: bool) -> bool {
+        match self.passes.iter_mut().find(|p| p.effect.name() == name) {
+            Some(pass) => {
+                pass.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Flips the pass called `name`, returning whether it is now enabled.
+    pub fn toggle(&mut self, name: &str) -> Option<bool> {
+        let pass = self.passes.iter_mut().find(|p| p.effect.name() == name)?;
+        pass.enabled = !pass.enabled;
+        Some(pass.enabled)
+    }
+
+    /// Names of the passes in order.
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.passes.iter().map(|pass| pass.effect.name())
+    }
+
+    pub fn reload_shaders(&mut self) {
+        for pass in &mut self.passes {
+            pass.effect.reload_shaders();
+        }
+    }
+
+    pub fn check_uniforms(&self) {
+        for pass in &self.passes {
+            pass.effect.check_uniforms();
+        }
+    }
+
+    /// Reallocates every target, e.g. after the window was resized.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        for target in self.targets.iter_mut().chain(&mut self.multisampled) {
+            target.resize(width, height)?;
+        }
+
+        for pass in &mut self.passes {
+            pass.effect.resize(width, height)?;
+        }
+
+        Ok(())
+    }
+
+    /// Redirects drawing into the HDR scene target until `finish`.
+    pub fn begin(&mut self) {
+        self.output = Framebuffer::current();
+        self.multisampled
+            .as_ref()
+            .unwrap_or(&self.targets[0])
+            .bind();
+    }
+
+    /// Runs the enabled passes over the scene, the last one into the framebuffer
+    /// and viewport that were bound at `begin`.
+    pub fn finish(&mut self) {
+        let enabled: Vec<usize> = (0..self.passes.len())
+            .filter(|&i| self.passes[i].enabled)
+            .collect();
+        let mut current = 0;
+
+        if let Some(multisampled) = &self.multisampled {
+            multisampled.blit(&self.targets[0], gl::COLOR_BUFFER_BIT);
+        }
+
+        unsafe {
+            gl::Disable(gl::DEPTH_TEST);
+        }
+
+        for (n, &i) in enabled.iter().enumerate() {
+            let input = current;
+
+            if n + 1 == enabled.len() {
+                self.output.bind();
+            } else {
+                current = self.next_target(current, self.passes[i].effect.output());
+                self.targets[current].bind();
+            }
+
+            self.passes[i]
+                .effect
+                .apply(&self.targets[input], &self.fullscreen);
+        }
+
+        if enabled.is_empty() {
+            // nothing draws the scene, so copy it as is
+            self.targets[0].blit_into(&self.output, gl::COLOR_BUFFER_BIT);
+            self.output.bind();
+        }
+
+        unsafe {
+            gl::Enable(gl::DEPTH_TEST);
+        }
+    }
+
+    /// A target of `precision` other than the one at `current`.
+    fn next_target(&self, current: usize, precision: Precision) -> usize {
+        (0..self.targets.len())
+            .find(|&i| i != current && self.targets[i].color_format(0) == precision.format())
+            .unwrap_or(current)
+    }
+}
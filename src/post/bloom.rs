@@ -0,0 +1,154 @@
+//! Glow around colors brighter than the display can show: the bright parts are
+//! extracted at half resolution, blurred and added back.
+
+use crate::{
+    graphics::Fullscreen,
+    post::{self, Effect, Precision},
+    render_target::{Framebuffer, RenderTarget},
+    shader::{shader_program::ShaderProgram, uniforms::Uniforms},
+};
+use anyhow::Result;
+use glm::Vec2;
+
+#[derive(Uniforms)]
+struct BrightUniforms {
+    scene: i32,
+    threshold: f32,
+    knee: f32,
+}
+
+#[derive(Uniforms)]
+struct BlurUniforms {
+    image: i32,
+    direction: Vec2,
+}
+
+#[derive(Uniforms)]
+struct CompositeUniforms {
+    scene: i32,
+    bloom: i32,
+    strength: f32,
+}
+
+pub struct Bloom {
+    /// Half-resolution targets the blur alternates between.
+    targets: [RenderTarget; 2],
+    shader_bright: ShaderProgram,
+    shader_blur: ShaderProgram,
+    shader_composite: ShaderProgram,
+    /// Brightness from which colors bloom.
+    pub threshold: f32,
+    /// Width of the soft transition below `threshold`.
+    pub knee: f32,
+    /// Share of the blurred colors added back.
+    pub strength: f32,
+}
+
+impl Bloom {
+    /// Horizontal and vertical blur pairs; each widens the glow.
+    const BLUR_PASSES: usize = 4;
+
+    /// `width` and `height` are those of the [`super::PostStack`].
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        let (width, height) = Self::half(width, height);
+        let target = || post::color_target(width, height, Precision::Hdr);
+        let program = |fs| ShaderProgram::new("fullscreen.vs", fs);
+
+        Ok(Self {
+            targets: [target()?, target()?],
+            shader_bright: program("bloom_bright.fs")?,
+            shader_blur: program("blur.fs")?,
+            shader_composite: program("bloom_composite.fs")?,
+            threshold: 1.,
+            knee: 0.5,
+            strength: 0.6,
+        })
+    }
+
+    fn half(width: u32, height: u32) -> (u32, u32) {
+        ((width / 2).max(1), (height / 2).max(1))
+    }
+
+    /// Binds `target`, which also sets the viewport to half resolution.
+    fn draw_into(&self, target: usize, input: &RenderTarget, fullscreen: &Fullscreen) {
+        self.targets[target].bind();
+        input.bind_color(0, 0);
+        fullscreen.draw();
+    }
+}
+
+impl Effect for Bloom {
+    fn name(&self) -> &'static str {
+        "bloom"
+    }
+
+    fn output(&self) -> Precision {
+        Precision::Hdr
+    }
+
+    fn apply(&mut self, input: &RenderTarget, fullscreen: &Fullscreen) {
+        let output = Framebuffer::current();
+
+        self.shader_bright.enable();
+        BrightUniforms {
+            scene: 0,
+            threshold: self.threshold,
+            knee: self.knee,
+        }
+        .apply(&self.shader_bright);
+        self.draw_into(0, input, fullscreen);
+
+        self.shader_blur.enable();
+        let target = &self.targets[0];
+        let texel = glm::vec2(1. / target.width() as f32, 1. / target.height() as f32);
+
+        for _ in 0..Self::BLUR_PASSES {
+            for (from, to, direction) in [
+                (0, 1, glm::vec2(texel.x, 0.)),
+                (1, 0, glm::vec2(0., texel.y)),
+            ] {
+                BlurUniforms {
+                    image: 0,
+                    direction,
+                }
+                .apply(&self.shader_blur);
+                self.draw_into(to, &self.targets[from], fullscreen);
+            }
+        }
+
+        output.bind();
+        input.bind_color(0, 0);
+        self.targets[0].bind_color(0, 1);
+
+        self.shader_composite.enable();
+        CompositeUniforms {
+            scene: 0,
+            bloom: 1,
+            strength: self.strength,
+        }
+        .apply(&self.shader_composite);
+        fullscreen.draw();
+    }
+
+    fn reload_shaders(&mut self) {
+        self.shader_bright.reload();
+        self.shader_blur.reload();
+        self.shader_composite.reload();
+    }
+
+    fn check_uniforms(&self) {
+        self.shader_bright.check_uniforms();
+        self.shader_blur.check_uniforms();
+        self.shader_composite.check_uniforms();
+    }
+
+    fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        let (width, height) = Self::half(width, height);
+
+        for target in &mut self.targets {
+            target.resize(width, height)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+//! Color grading through a 3D lookup table indexed by display colors, as
+//! exported by grading tools.
+
+use crate::{
+    graphics::Fullscreen,
+    post::{Effect, Precision},
+    render_target::RenderTarget,
+    shader::{shader_program::ShaderProgram, uniforms::Uniforms},
+    texture::Texture,
+};
+use anyhow::Result;
+
+#[derive(Uniforms)]
+struct ColorGradeUniforms<'a> {
+    image: i32,
+    lut: &'a Texture,
+    amount: f32,
+}
+
+pub struct ColorGrade {
+    program: ShaderProgram,
+    lut: Texture,
+    /// Blend from the ungraded (0) to the graded (1) colors.
+    pub amount: f32,
+}
+
+impl ColorGrade {
+    /// Size of the identity table used until [`Self::set_lut`].
+    const IDENTITY_SIZE: u32 = 16;
+
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            program: ShaderProgram::new("fullscreen.vs", "color_grade.fs")?,
+            lut: Texture::identity_lut(Self::IDENTITY_SIZE),
+            amount: 1.,
+        })
+    }
+
+    /// Grades through `lut`, see [`Texture::lut`].
+    pub fn set_lut(&mut self, lut: Texture) {
+        self.lut = lut;
+    }
+}
+
+impl Effect for ColorGrade {
+    fn name(&self) -> &'static str {
+        "grade"
+    }
+
+    fn output(&self) -> Precision {
+        Precision::Ldr
+    }
+
+    fn apply(&mut self, input: &RenderTarget, fullscreen: &Fullscreen) {
+        input.bind_color(0, 0);
+        self.program.enable();
+        ColorGradeUniforms {
+            image: 0,
+            lut: &self.lut,
+            amount: self.amount,
+        }
+        .apply(&self.program);
+        fullscreen.draw();
+    }
+
+    fn reload_shaders(&mut self) {
+        self.program.reload();
+    }
+
+    fn check_uniforms(&self) {
+        self.program.check_uniforms();
+    }
+}
@@ -0,0 +1,52 @@
+//! Fast approximate anti-aliasing: blurs along edges found in the luma of the
+//! final display colors.
+
+use crate::{
+    graphics::Fullscreen,
+    post::{Effect, Precision},
+    render_target::RenderTarget,
+    shader::{shader_program::ShaderProgram, uniforms::Uniforms},
+};
+use anyhow::Result;
+
+#[derive(Uniforms)]
+struct FxaaUniforms {
+    image: i32,
+}
+
+pub struct Fxaa {
+    program: ShaderProgram,
+}
+
+impl Fxaa {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            program: ShaderProgram::new("fullscreen.vs", "fxaa.fs")?,
+        })
+    }
+}
+
+impl Effect for Fxaa {
+    fn name(&self) -> &'static str {
+        "fxaa"
+    }
+
+    fn output(&self) -> Precision {
+        Precision::Ldr
+    }
+
+    fn apply(&mut self, input: &RenderTarget, fullscreen: &Fullscreen) {
+        input.bind_color(0, 0);
+        self.program.enable();
+        FxaaUniforms { image: 0 }.apply(&self.program);
+        fullscreen.draw();
+    }
+
+    fn reload_shaders(&mut self) {
+        self.program.reload();
+    }
+
+    fn check_uniforms(&self) {
+        self.program.check_uniforms();
+    }
+}
@@ -0,0 +1,113 @@
+//! Maps the HDR scene into displayable range and encodes sRGB, the pass between
+//! the HDR and LDR effects.
+
+use crate::{
+    graphics::Fullscreen,
+    post::{Effect, Precision},
+    render_target::RenderTarget,
+    shader::{preprocessor::Defines, shader_program::ShaderProgram, uniforms::Uniforms},
+};
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// Curve compressing HDR colors into `[0, 1]`, applied after the exposure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToneMapper {
+    /// `c / (1 + c)`, soft but desaturating.
+    Reinhard,
+    /// Filmic curve fitted to the ACES reference transform.
+    #[default]
+    Aces,
+    /// `1 - exp(-c)`, a plain photographic exposure.
+    Exposure,
+}
+
+impl ToneMapper {
+    const ALL: [Self; 3] = [Self::Reinhard, Self::Aces, Self::Exposure];
+
+    /// The next mapper, wrapping around.
+    pub fn next(self) -> Self {
+        Self::ALL[(self as usize + 1) % Self::ALL.len()]
+    }
+
+    /// Each mapper's index, as compared with `toneMapper` in `tonemap.fs`.
+    fn defines() -> Defines {
+        Defines::new()
+            .define("REINHARD", Self::Reinhard as i32)
+            .define("ACES", Self::Aces as i32)
+            .define("EXPOSURE", Self::Exposure as i32)
+    }
+}
+
+impl FromStr for ToneMapper {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "reinhard" => Ok(Self::Reinhard),
+            "aces" => Ok(Self::Aces),
+            "exposure" => Ok(Self::Exposure),
+            _ => Err(anyhow!(
+                "unknown tone mapper {s}, expected reinhard, aces or exposure"
+            )),
+        }
+    }
+}
+
+#[derive(Uniforms)]
+struct ToneMapUniforms {
+    hdr: i32,
+    tone_mapper: i32,
+    exposure: f32,
+}
+
+pub struct ToneMapping {
+    program: ShaderProgram,
+    pub mapper: ToneMapper,
+    /// Scales scene colors before mapping; 1 leaves them as lit.
+    pub exposure: f32,
+}
+
+impl ToneMapping {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            program: ShaderProgram::with_defines(
+                "fullscreen.vs",
+                "tonemap.fs",
+                &ToneMapper::defines(),
+            )?,
+            mapper: ToneMapper::default(),
+            exposure: 1.,
+        })
+    }
+}
+
+impl Effect for ToneMapping {
+    fn name(&self) -> &'static str {
+        "tonemap"
+    }
+
+    fn output(&self) -> Precision {
+        Precision::Ldr
+    }
+
+    fn apply(&mut self, input: &RenderTarget, fullscreen: &Fullscreen) {
+        input.bind_color(0, 0);
+        self.program.enable();
+        ToneMapUniforms {
+            hdr: 0,
+            tone_mapper: self.mapper as i32,
+            exposure: self.exposure,
+        }
+        .apply(&self.program);
+        fullscreen.draw();
+    }
+
+    fn reload_shaders(&mut self) {
+        self.program.reload();
+    }
+
+    fn check_uniforms(&self) {
+        self.program.check_uniforms();
+    }
+}
@@ -0,0 +1,70 @@
+//! Darkens the corners of the frame.
+
+use crate::{
+    graphics::Fullscreen,
+    post::{Effect, Precision},
+    render_target::RenderTarget,
+    shader::{shader_program::ShaderProgram, uniforms::Uniforms},
+};
+use anyhow::Result;
+
+#[derive(Uniforms)]
+struct VignetteUniforms {
+    image: i32,
+    radius: f32,
+    softness: f32,
+    strength: f32,
+}
+
+pub struct Vignette {
+    program: ShaderProgram,
+    /// Distance from the center, in halves of the frame height, where
+    /// darkening starts.
+    pub radius: f32,
+    /// Distance over which it fades in.
+    pub softness: f32,
+    /// Darkening at full effect, from 0 (none) to 1 (black).
+    pub strength: f32,
+}
+
+impl Vignette {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            program: ShaderProgram::new("fullscreen.vs", "vignette.fs")?,
+            radius: 0.9,
+            softness: 0.8,
+            strength: 0.5,
+        })
+    }
+}
+
+impl Effect for Vignette {
+    fn name(&self) -> &'static str {
+        "vignette"
+    }
+
+    fn output(&self) -> Precision {
+        Precision::Ldr
+    }
+
+    fn apply(&mut self, input: &RenderTarget, fullscreen: &Fullscreen) {
+        input.bind_color(0, 0);
+        self.program.enable();
+        VignetteUniforms {
+            image: 0,
+            radius: self.radius,
+            softness: self.softness,
+            strength: self.strength,
+        }
+        .apply(&self.program);
+        fullscreen.draw();
+    }
+
+    fn reload_shaders(&mut self) {
+        self.program.reload();
+    }
+
+    fn check_uniforms(&self) {
+        self.program.check_uniforms();
+    }
+}
@@ -0,0 +1,573 @@
+//! Framebuffer objects owning their attachments: any number of color
+//! attachments, an optional depth (and stencil) attachment and multisampling,
+//! or cube map and array attachments drawn one layer at a time.
+//! [`Framebuffer`] names any framebuffer drawn into, the window's included.
+
+use crate::{texture::Filter, util};
+use anyhow::{anyhow, Result};
+use gl::types::{GLbitfield, GLenum, GLint, GLuint};
+use std::ptr;
+
+/// Storage format of an attachment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Rgba8,
+    /// Encodes to sRGB on write when `GL_FRAMEBUFFER_SRGB` is enabled.
+    Srgb8Alpha8,
+    Rgba16F,
+    Rgba32F,
+    Depth24,
+    Depth32F,
+    Depth24Stencil8,
+}
+
+impl Format {
+    /// Internal format, format and type for `glTexImage2D`.
+    fn formats(self) -> (GLenum, GLenum, GLenum) {
+        match self {
+            Self::Rgba8 => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            Self::Srgb8Alpha8 => (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            Self::Rgba16F => (gl::RGBA16F, gl::RGBA, gl::FLOAT),
+            Self::Rgba32F => (gl::RGBA32F, gl::RGBA, gl::FLOAT),
+            Self::Depth24 => (gl::DEPTH_COMPONENT24, gl::DEPTH_COMPONENT, gl::UNSIGNED_INT),
+            Self::Depth32F => (gl::DEPTH_COMPONENT32F, gl::DEPTH_COMPONENT, gl::FLOAT),
+            Self::Depth24Stencil8 => (
+                gl::DEPTH24_STENCIL8,
+                gl::DEPTH_STENCIL,
+                gl::UNSIGNED_INT_24_8,
+            ),
+        }
+    }
+
+    pub fn is_depth(self) -> bool {
+        matches!(self, Self::Depth24 | Self::Depth32F | Self::Depth24Stencil8)
+    }
+
+    fn attachment(self) -> GLenum {
+        match self {
+            Self::Depth24Stencil8 => gl::DEPTH_STENCIL_ATTACHMENT,
+            Self::Depth24 | Self::Depth32F => gl::DEPTH_ATTACHMENT,
+            _ => gl::COLOR_ATTACHMENT0,
+        }
+    }
+}
+
+/// Shape of the attachment textures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layers {
+    #[default]
+    Single,
+    /// Six square faces in the order of `GL_TEXTURE_CUBE_MAP_POSITIVE_X` onwards.
+    Cube,
+    /// A 2D array texture with this many layers.
+    Array(u32),
+}
+
+impl Layers {
+    fn target(self) -> GLenum {
+        match self {
+            Self::Single => gl::TEXTURE_2D,
+            Self::Cube => gl::TEXTURE_CUBE_MAP,
+            Self::Array(_) => gl::TEXTURE_2D_ARRAY,
+        }
+    }
+
+    /// Attaches `layer` of `texture` to the bound framebuffer.
+    fn attach(self, attachment: GLenum, texture: GLuint, layer: u32) {
+        unsafe {
+            match self {
+                Self::Single => gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    attachment,
+                    gl::TEXTURE_2D,
+                    texture,
+                    0,
+                ),
+                Self::Cube => gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    attachment,
+                    gl::TEXTURE_CUBE_MAP_POSITIVE_X + layer,
+                    texture,
+                    0,
+                ),
+                Self::Array(_) => {
+                    gl::FramebufferTextureLayer(gl::FRAMEBUFFER, attachment, texture, 0, layer as _)
+                }
+            }
+        }
+    }
+}
+
+/// A framebuffer object and the viewport drawn within it. Passes that redirect
+/// drawing capture the [`Self::current`] one and bind it again when done.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Framebuffer {
+    /// 0 for the window.
+    pub fbo: GLuint,
+    /// As by `GL_VIEWPORT`: x, y, width and height.
+    pub viewport: [GLint; 4],
+}
+
+impl Framebuffer {
+    /// The framebuffer and viewport bound for drawing.
+    pub fn current() -> Self {
+        let mut fbo: GLint = 0;
+        let mut viewport = [0; 4];
+
+        unsafe {
+            gl::GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut fbo);
+            gl::GetIntegerv(gl::VIEWPORT, viewport.as_mut_ptr());
+        }
+
+        Self {
+            fbo: fbo as _,
+            viewport,
+        }
+    }
+
+    /// Binds the framebuffer for drawing and sets its viewport.
+    pub fn bind(&self) {
+        let [x, y, width, height] = self.viewport;
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(x, y, width, height);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderTargetBuilder {
+    width: u32,
+    height: u32,
+    colors: Vec<Format>,
+    depth: Option<Format>,
+    samples: u32,
+    filter: Filter,
+    layers: Layers,
+    compare: bool,
+}
+
+impl RenderTargetBuilder {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            colors: Vec::new(),
+            depth: None,
+            samples: 1,
+            filter: Filter::Linear,
+            layers: Layers::Single,
+            compare: false,
+        }
+    }
+
+    /// Adds a color attachment, written by fragment output `n` for the `n`th call.
+    pub fn color(mut self, format: Format) -> Self {
+        self.colors.push(format);
+        self
+    }
+
+    /// Sets the depth attachment, with stencil for [`Format::Depth24Stencil8`].
+    pub fn depth(mut self, format: Format) -> Self {
+        self.depth = Some(format);
+        self
+    }
+
+    /// Samples per pixel; above 1 the attachments are renderbuffers, read by
+    /// blitting into a single-sampled target.
+    pub fn samples(mut self, samples: u32) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    /// Filtering of the attachments when sampled; trilinear is treated as linear.
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    /// Makes every attachment a cube map or an array; draw into one layer at a
+    /// time with [`RenderTarget::bind_layer`]. Cube faces must be square.
+    pub fn layers(mut self, layers: Layers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Samples the depth attachment through a shadow sampler, comparing with
+    /// `GL_LEQUAL`; lookups outside the target pass.
+    pub fn compare(mut self) -> Self {
+        self.compare = true;
+        self
+    }
+
+    pub fn build(self) -> Result<RenderTarget> {
+        if let Some(&format) = self.colors.iter().find(|format| format.is_depth()) {
+            Err(anyhow!("{format:?} is not a color format"))?
+        }
+
+        if let Some(format) = self.depth.filter(|format| !format.is_depth()) {
+            Err(anyhow!("{format:?} is not a depth format"))?
+        }
+
+        if self.layers != Layers::Single && self.samples > 1 {
+            Err(anyhow!("{:?} targets cannot be multisampled", self.layers))?
+        }
+
+        if self.layers == Layers::Cube && self.width != self.height {
+            Err(anyhow!(
+                "cube faces must be square, not {}x{}",
+                self.width,
+                self.height
+            ))?
+        }
+
+        let mut fbo = 0;
+
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo as _);
+        }
+
+        let mut target = RenderTarget {
+            fbo,
+            colors: Vec::new(),
+            depth: None,
+            config: self,
+        };
+        target.allocate()?;
+        Ok(target)
+    }
+
+    /// Creates storage of `format` and attaches it to the bound framebuffer.
+    fn attach(&self, format: Format, attachment: GLenum) -> Storage {
+        let (internal, pixel_format, kind) = format.formats();
+        let (width, height) = (self.width as _, self.height as _);
+        let mut name = 0;
+
+        unsafe {
+            if self.samples > 1 {
+                gl::GenRenderbuffers(1, &mut name);
+                gl::BindRenderbuffer(gl::RENDERBUFFER, name);
+                gl::RenderbufferStorageMultisample(
+                    gl::RENDERBUFFER,
+                    self.samples as _,
+                    internal,
+                    width,
+                    height,
+                );
+                gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, attachment, gl::RENDERBUFFER, name);
+                return Storage::Renderbuffer(name);
+            }
+
+            let filter = match self.filter {
+                Filter::Nearest => gl::NEAREST,
+                Filter::Linear | Filter::Trilinear => gl::LINEAR,
+            };
+
+            let target = self.layers.target();
+            let image = |target, depth| match depth {
+                None => gl::TexImage2D(
+                    target,
+                    0,
+                    internal as _,
+                    width,
+                    height,
+                    0,
+                    pixel_format,
+                    kind,
+                    ptr::null(),
+                ),
+                Some(depth) => gl::TexImage3D(
+                    target,
+                    0,
+                    internal as _,
+                    width,
+                    height,
+                    depth,
+                    0,
+                    pixel_format,
+                    kind,
+                    ptr::null(),
+                ),
+            };
+
+            gl::GenTextures(1, &mut name);
+            gl::BindTexture(target, name);
+
+            match self.layers {
+                Layers::Single => image(target, None),
+                Layers::Cube => {
+                    for face in 0..6 {
+                        image(gl::TEXTURE_CUBE_MAP_POSITIVE_X + face, None);
+                    }
+                }
+                Layers::Array(layers) => image(target, Some(layers as _)),
+            }
+
+            let wrap = if self.compare && format.is_depth() {
+                gl::TexParameterfv(target, gl::TEXTURE_BORDER_COLOR, [1.; 4].as_ptr());
+                gl::TexParameteri(
+                    target,
+                    gl::TEXTURE_COMPARE_MODE,
+                    gl::COMPARE_REF_TO_TEXTURE as _,
+                );
+                gl::TexParameteri(target, gl::TEXTURE_COMPARE_FUNC, gl::LEQUAL as _);
+                gl::CLAMP_TO_BORDER
+            } else {
+                gl::CLAMP_TO_EDGE
+            };
+
+            gl::TexParameteri(target, gl::TEXTURE_MIN_FILTER, filter as _);
+            gl::TexParameteri(target, gl::TEXTURE_MAG_FILTER, filter as _);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_S, wrap as _);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_T, wrap as _);
+            gl::TexParameteri(target, gl::TEXTURE_WRAP_R, wrap as _);
+            self.layers.attach(attachment, name, 0);
+            Storage::Texture(name)
+        }
+    }
+}
+
+/// Texture when single-sampled so that later passes can sample it.
+#[derive(Debug, Clone, Copy)]
+enum Storage {
+    Texture(GLuint),
+    Renderbuffer(GLuint),
+}
+
+impl Storage {
+    fn delete(self) {
+        unsafe {
+            match self {
+                Self::Texture(texture) => gl::DeleteTextures(1, &texture),
+                Self::Renderbuffer(renderbuffer) => gl::DeleteRenderbuffers(1, &renderbuffer),
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderTarget {
+    fbo: GLuint,
+    colors: Vec<Storage>,
+    depth: Option<Storage>,
+    config: RenderTargetBuilder,
+}
+
+impl RenderTarget {
+    /// Binds the target for drawing and sets the viewport to cover it.
+    pub fn bind(&self) {
+        self.framebuffer().bind();
+    }
+
+    /// Binds the target like [`Self::bind`] with `layer` of every attachment
+    /// attached: a cube face or an array layer as set by
+    /// [`RenderTargetBuilder::layers`].
+    pub fn bind_layer(&self, layer: u32) {
+        let layers = self.config.layers;
+        self.bind();
+
+        for (i, &storage) in (0..).zip(&self.colors) {
+            if let Storage::Texture(texture) = storage {
+                layers.attach(gl::COLOR_ATTACHMENT0 + i, texture, layer);
+            }
+        }
+
+        if let (Some(Storage::Texture(texture)), Some(format)) = (self.depth, self.config.depth) {
+            layers.attach(format.attachment(), texture, layer);
+        }
+    }
+
+    /// The framebuffer object with a viewport covering the whole target.
+    pub fn framebuffer(&self) -> Framebuffer {
+        Framebuffer {
+            fbo: self.fbo,
+            viewport: [0, 0, self.width() as _, self.height() as _],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.config.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.config.height
+    }
+
+    pub fn samples(&self) -> u32 {
+        self.config.samples
+    }
+
+    pub fn color_format(&self, index: usize) -> Format {
+        self.config.colors[index]
+    }
+
+    /// The texture of color attachment `index`; `None` when multisampled.
+    pub fn color_texture(&self, index: usize) -> Option<GLuint> {
+        match self.colors[index] {
+            Storage::Texture(texture) => Some(texture),
+            Storage::Renderbuffer(_) => None,
+        }
+    }
+
+    /// Binds color attachment `index` to texture `unit`.
+    ///
+    /// # Panics
+    /// If the target is multisampled.
+    pub fn bind_color(&self, index: usize, unit: GLuint) {
+        let texture = self
+            .color_texture(index)
+            .expect("multisampled attachments cannot be sampled");
+        self.bind_texture(texture, unit);
+    }
+
+    /// Binds the depth attachment to texture `unit`.
+    ///
+    /// # Panics
+    /// If there is no depth attachment or the target is multisampled.
+    pub fn bind_depth(&self, unit: GLuint) {
+        let Some(Storage::Texture(texture)) = self.depth else {
+            panic!("no depth attachment to sample");
+        };
+        self.bind_texture(texture, unit);
+    }
+
+    /// Reallocates every attachment at the new size; contents are lost.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        if (width, height) == (self.width(), self.height()) {
+            return Ok(());
+        }
+
+        self.release();
+        self.config.width = width;
+        self.config.height = height;
+        self.allocate()
+    }
+
+    /// Copies the `mask` buffers (`gl::COLOR_BUFFER_BIT` and the like) of the
+    /// first color attachment into the same attachment of `other`, resolving
+    /// multisampling. Sizes may differ for color, which is filtered linearly.
+    pub fn blit(&self, other: &RenderTarget, mask: GLbitfield) {
+        self.blit_into(&other.framebuffer(), mask);
+    }
+
+    /// Like [`Self::blit`], into the viewport of any framebuffer, e.g. the window.
+    /// The framebuffer bound before the call is bound again.
+    pub fn blit_into(&self, target: &Framebuffer, mask: GLbitfield) {
+        let current = Framebuffer::current();
+        let [x, y, width, height] = target.viewport;
+        let filter = if mask == gl::COLOR_BUFFER_BIT {
+            gl::LINEAR
+        } else {
+            gl::NEAREST
+        };
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            gl::BindFramebuffer(gl::DRAW_FRAMEBUFFER, target.fbo);
+            gl::BlitFramebuffer(
+                0,
+                0,
+                self.width() as _,
+                self.height() as _,
+                x,
+                y,
+                x + width,
+                y + height,
+                mask,
+                filter,
+            );
+        }
+
+        current.bind();
+    }
+
+    /// Reads the first color attachment back as tightly packed RGBA8 rows, top
+    /// row first, resolving multisampling first. The framebuffer bound before the
+    /// call is bound again.
+    pub fn read_pixels(&self) -> Result<Vec<u8>> {
+        if self.samples() > 1 {
+            let resolved = RenderTargetBuilder::new(self.width(), self.height())
+                .color(Format::Rgba8)
+                .build()?;
+            self.blit(&resolved, gl::COLOR_BUFFER_BIT);
+            return resolved.read_pixels();
+        }
+
+        let current = Framebuffer::current();
+
+        unsafe {
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, self.fbo);
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+        }
+
+        let pixels = util::read_pixels(self.width(), self.height());
+        current.bind();
+        Ok(pixels)
+    }
+
+    fn bind_texture(&self, texture: GLuint, unit: GLuint) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.config.layers.target(), texture);
+        }
+    }
+
+    /// Creates and attaches storage for every configured attachment, leaving the
+    /// bound framebuffer as it was.
+    fn allocate(&mut self) -> Result<()> {
+        let config = &self.config;
+        let current = Framebuffer::current();
+
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+        }
+
+        self.colors = (0..config.colors.len() as GLenum)
+            .zip(&config.colors)
+            .map(|(i, &format)| config.attach(format, gl::COLOR_ATTACHMENT0 + i))
+            .collect();
+        self.depth = config
+            .depth
+            .map(|format| config.attach(format, format.attachment()));
+
+        unsafe {
+            let draw_buffers: Vec<GLenum> = (0..config.colors.len() as GLenum)
+                .map(|i| gl::COLOR_ATTACHMENT0 + i)
+                .collect();
+
+            if draw_buffers.is_empty() {
+                gl::DrawBuffer(gl::NONE);
+                gl::ReadBuffer(gl::NONE);
+            } else {
+                gl::DrawBuffers(draw_buffers.len() as _, draw_buffers.as_ptr());
+            }
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            current.bind();
+
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                Err(anyhow!("render target incomplete (status {status:#x})"))?
+            }
+        }
+
+        Ok(())
+    }
+
+    fn release(&mut self) {
+        for storage in self.colors.drain(..).chain(self.depth.take()) {
+            storage.delete();
+        }
+    }
+}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        self.release();
+
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+        }
+    }
+}
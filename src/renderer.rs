@@ -0,0 +1,415 @@
+use crate::{
+    cluster::ClusterGrid,
+    deferred::Deferred,
+    graphics::{self, Graphics, Instance, LightCube, Lighting},
+    lights::{self, LightBuffer},
+    material::MaterialMaps,
+    particles::Particles,
+    post::{
+        bloom::Bloom,
+        color_grade::ColorGrade,
+        fxaa::Fxaa,
+        tonemap::{ToneMapper, ToneMapping},
+        vignette::Vignette,
+        PostStack,
+    },
+    scene::Scene,
+    shader::{
+        shader_program::{ProgramBuilder, ShaderProgram},
+        uniforms::Uniforms,
+    },
+    shadow::ShadowMaps,
+    texture::{ColorSpace, Texture},
+};
+use anyhow::Result;
+use glm::ext as gle;
+use glm::{Mat4, Vec3};
+use num_traits::One;
+use std::iter;
+
+/// How lit geometry is shaded; chosen once at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pipeline {
+    /// Clustered forward shading in `lighting.fs`.
+    #[default]
+    Forward,
+    /// A G-buffer pass followed by one light volume per light. Shadows and the
+    /// sun are not applied. The G-buffer is single-sampled, so multisampling only
+    /// smooths what is drawn after the light pass, not the lit geometry.
+    Deferred,
+}
+
+/// The camera of every pass drawing the scene from the viewer.
+#[derive(Uniforms)]
+pub struct CameraUniforms {
+    pub view: Mat4,
+    pub projection: Mat4,
+}
+
+impl CameraUniforms {
+    pub fn new(scene: &Scene) -> Self {
+        Self {
+            view: scene.camera.view(),
+            projection: scene.projection,
+        }
+    }
+}
+
+/// Per-frame state of `lighting.fs` besides lights, clusters and shadows.
+#[derive(Uniforms)]
+struct LightingUniforms {
+    #[uniform(flatten)]
+    camera: CameraUniforms,
+    view_pos: Vec3,
+    fog_color: Vec3,
+    ambient_color: Vec3,
+    time: f32,
+}
+
+#[derive(Uniforms)]
+struct LightCubeUniforms {
+    #[uniform(flatten)]
+    camera: CameraUniforms,
+    intensity: f32,
+}
+
+#[derive(Uniforms)]
+struct NormalsUniforms {
+    #[uniform(flatten)]
+    camera: CameraUniforms,
+    normal_length: f32,
+}
+
+/// Cubes sharing texture maps, drawn with one instanced call.
+struct Batch {
+    maps: MaterialMaps,
+    graphics: Graphics<Lighting>,
+    instances: Vec<Instance>,
+}
+
+/// The lit geometry of a frame: the floor and cubes grouped by their maps, and
+/// the models.
+struct Geometry {
+    batches: Vec<Batch>,
+    /// Stands in for missing maps.
+    white: Texture,
+}
+
+impl Geometry {
+    fn new() -> Self {
+        Self {
+            batches: Vec::new(),
+            white: Texture::from_rgba8(1, 1, &[255; 4], ColorSpace::Linear),
+        }
+    }
+
+    fn update(&mut self, scene: &Scene) {
+        for batch in &mut self.batches {
+            batch.instances.clear();
+        }
+
+        for cube in iter::once(&scene.floor).chain(&scene.cubes) {
+            let maps = &cube.material.maps;
+            let index = match self.batches.iter().position(|b| b.maps.same(maps)) {
+                Some(index) => index,
+                None => {
+                    self.batches.push(Batch {
+                        maps: maps.clone(),
+                        graphics: Graphics::<Lighting>::new(graphics::CUBE_VERTICES)
+                            .with_indices(graphics::CUBE_INDICES)
+                            .with_instances(&Instance::layout()),
+                        instances: Vec::new(),
+                    });
+                    self.batches.len() - 1
+                }
+            };
+
+            let instance = Instance::with_material(&cube.model(), &cube.material);
+            self.batches[index].instances.push(instance);
+        }
+
+        // drop batches whose maps went out of use, releasing the textures
+        self.batches.retain(|batch| !batch.instances.is_empty());
+
+        for batch in &mut self.batches {
+            batch.graphics.set_instances(&batch.instances);
+        }
+    }
+
+    /// Draws everything with the current program, binding the maps of each draw
+    /// to `program` if it samples them.
+    fn draw(&self, scene: &Scene, program: Option<&ShaderProgram>) {
+        for batch in &self.batches {
+            if let Some(program) = program {
+                batch.maps.bind(program, &self.white);
+            }
+
+            batch.graphics.draw_instanced();
+        }
+
+        for model in &scene.models {
+            if let Some(program) = program {
+                model.material().maps.bind(program, &self.white);
+            }
+
+            model.draw();
+        }
+    }
+}
+
+pub struct Renderer {
+    geometry: Geometry,
+    light_cube: Graphics<LightCube>,
+    shader_lighting: ShaderProgram,
+    shader_light_cube: ShaderProgram,
+    shader_normals: ShaderProgram,
+    show_normals: bool,
+    lights: LightBuffer,
+    clusters: ClusterGrid,
+    shadows: ShadowMaps,
+    instances: Vec<Instance>,
+    deferred: Option<Deferred>,
+    particles: Option<Particles>,
+    post: PostStack,
+}
+
+impl Renderer {
+    /// World-space length of the lines drawn by [`Self::set_show_normals`].
+    const NORMAL_LENGTH: f32 = 0.2;
+    /// Scales the colors of the light markers, bright enough in HDR to bloom.
+    const MARKER_INTENSITY: f32 = 4.;
+
+    /// The scene is drawn with `samples` per pixel, see [`Pipeline::Deferred`]
+    /// for what that covers there. `width` and `height` size the
+    /// post-processing targets and the G-buffer of the deferred pipeline and must
+    /// match the framebuffer drawn into.
+    pub fn new(pipeline: Pipeline, samples: u32, width: u32, height: u32) -> Result<Self> {
+        let defines = ShadowMaps::defines(lights::defines());
+        let shader_lighting = ShaderProgram::with_defines("lighting.vs", "lighting.fs", &defines)?;
+
+        Ok(Self {
+            geometry: Geometry::new(),
+            light_cube: Graphics::<LightCube>::new(graphics::CUBE_VERTICES)
+                .with_indices(graphics::CUBE_INDICES)
+                .with_instances(&Instance::layout()),
+            shader_lighting,
+            shader_light_cube: ShaderProgram::new("light_cube.vs", "light_cube.fs")?,
+            shader_normals: ProgramBuilder::new()
+                .vertex("normals.vs")
+                .geometry("normals.gs")
+                .fragment("normals.fs")
+                .build()?,
+            show_normals: false,
+            lights: LightBuffer::new(),
+            clusters: ClusterGrid::new(),
+            shadows: ShadowMaps::new(0)?,
+            instances: Vec::new(),
+            deferred: match pipeline {
+                Pipeline::Forward => None,
+                Pipeline::Deferred => Some(Deferred::new(width, height, &defines)?),
+            },
+            particles: None,
+            post: PostStack::new(width, height, samples)?
+                .with_effect(Bloom::new(width, height)?, true)
+                .with_effect(ToneMapping::new()?, true)
+                .with_effect(ColorGrade::new()?, false)
+                .with_effect(Vignette::new()?, true)
+                .with_effect(Fxaa::new()?, true),
+        })
+    }
+
+    /// Reallocates every target for a framebuffer of the new size.
+    pub fn resize(&mut self, width: u32, height: u32) -> Result<()> {
+        self.post.resize(width, height)?;
+
+        if let Some(deferred) = &mut self.deferred {
+            deferred.resize(width, height)?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets how many of the lights contributing most at the camera cast shadows;
+    /// none do by default, as each costs six depth passes per frame. Fails if their
+    /// shadow maps cannot be allocated, leaving the previous count in place.
+    pub fn set_shadowed_lights(&mut self, count: usize) -> Result<()> {
+        self.shadows.set_point_lights(count)
+    }
+
+    /// Sprays `count` compute-simulated sparks from the middle of the floor, or
+    /// none for 0. Fails on contexts without compute shaders, leaving the
+    /// previous particles in place.
+    pub fn set_particles(&mut self, count: usize) -> Result<()> {
+        self.particles = match count {
+            0 => None,
+            _ => Some(Particles::new(count)?),
+        };
+
+        Ok(())
+    }
+
+    /// The passes between drawing and the framebuffer: bloom, tone mapping,
+    /// color grading, vignette and FXAA, all but grading enabled.
+    pub fn post(&self) -> &PostStack {
+        &self.post
+    }
+
+    pub fn post_mut(&mut self) -> &mut PostStack {
+        &mut self.post
+    }
+
+    pub fn set_tone_mapper(&mut self, mapper: ToneMapper) {
+        self.tone_mapping_mut().mapper = mapper;
+    }
+
+    pub fn tone_mapper(&self) -> ToneMapper {
+        self.post
+            .effect::<ToneMapping>()
+            .expect("the post stack tone maps")
+            .mapper
+    }
+
+    /// Scales scene colors before tone mapping; 1 leaves them as lit.
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.tone_mapping_mut().exposure = exposure;
+    }
+
+    fn tone_mapping_mut(&mut self) -> &mut ToneMapping {
+        self.post.effect_mut().expect("the post stack tone maps")
+    }
+
+    /// Overlays each vertex normal of the lit geometry as a short line.
+    pub fn set_show_normals(&mut self, show: bool) {
+        self.show_normals = show;
+    }
+
+    /// Rebuilds every program whose sources changed on disk; uniforms are set
+    /// each frame, so replaced programs need no further setup.
+    pub fn reload_shaders(&mut self) {
+        self.shader_lighting.reload();
+        self.shader_light_cube.reload();
+        self.shader_normals.reload();
+        self.shadows.reload_shaders();
+        self.post.reload_shaders();
+
+        if let Some(deferred) = &mut self.deferred {
+            deferred.reload_shaders();
+        }
+
+        if let Some(particles) = &mut self.particles {
+            particles.reload_shaders();
+        }
+    }
+
+    /// Reports uniform mistakes in every program used so far, see
+    /// [`ShaderProgram::check_uniforms`].
+    pub fn check_uniforms(&self) {
+        self.shader_lighting.check_uniforms();
+        self.shader_light_cube.check_uniforms();
+        self.shader_normals.check_uniforms();
+        self.shadows.check_uniforms();
+        self.post.check_uniforms();
+
+        if let Some(deferred) = &self.deferred {
+            deferred.check_uniforms();
+        }
+
+        if let Some(particles) = &self.particles {
+            particles.check_uniforms();
+        }
+    }
+
+    /// Draws `scene` into the currently bound framebuffer, lit in HDR and then
+    /// post-processed. Fails, before drawing anything, if the shadow maps of a
+    /// first sun cannot be allocated or the forward pipeline cannot address every
+    /// light.
+    pub fn draw(&mut self, scene: &Scene, t: f32) -> Result<()> {
+        let fog_color = scene.fog_color;
+        let view = scene.camera.view();
+
+        self.geometry.update(scene);
+
+        if self.deferred.is_none() {
+            self.lights
+                .update(&scene.light_positions, &scene.light_colors)?;
+            let geometry = &self.geometry;
+            self.shadows.render(scene, || geometry.draw(scene, None))?;
+        }
+
+        self.post.begin();
+
+        unsafe {
+            gl::ClearColor(fog_color.x, fog_color.y, fog_color.z, 1.);
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+        }
+
+        match &mut self.deferred {
+            Some(deferred) => {
+                deferred.begin(scene);
+                self.geometry.draw(scene, Some(deferred.geometry_program()));
+                deferred.resolve(scene);
+            }
+            None => {
+                self.clusters.update(
+                    &view,
+                    &scene.projection,
+                    &scene.light_positions,
+                    lights::radius(),
+                );
+
+                let shader_lighting = &self.shader_lighting;
+                shader_lighting.enable();
+                self.lights.bind(shader_lighting);
+                self.clusters.bind(shader_lighting);
+                self.shadows.bind(shader_lighting);
+                LightingUniforms {
+                    camera: CameraUniforms::new(scene),
+                    view_pos: scene.camera.position,
+                    fog_color,
+                    ambient_color: scene.ambient_color,
+                    time: t,
+                }
+                .apply(shader_lighting);
+                self.geometry.draw(scene, Some(shader_lighting));
+            }
+        }
+
+        if self.show_normals {
+            let shader_normals = &self.shader_normals;
+            shader_normals.enable();
+            NormalsUniforms {
+                camera: CameraUniforms::new(scene),
+                normal_length: Self::NORMAL_LENGTH,
+            }
+            .apply(shader_normals);
+            self.geometry.draw(scene, None);
+        }
+
+        if let Some(particles) = &mut self.particles {
+            particles.update(t);
+            particles.draw(scene);
+        }
+
+        let shader_light_cube = &self.shader_light_cube;
+        shader_light_cube.enable();
+        LightCubeUniforms {
+            camera: CameraUniforms::new(scene),
+            intensity: Self::MARKER_INTENSITY,
+        }
+        .apply(shader_light_cube);
+
+        self.instances.clear();
+        self.instances
+            .extend(iter::zip(&scene.light_positions, &scene.light_colors).map(
+                |(&pos, &color)| {
+                    let model = gle::scale(&gle::translate(&Mat4::one(), pos), Vec3::one() * 0.1);
+                    Instance::new(&model, color)
+                },
+            ));
+        self.light_cube.set_instances(&self.instances);
+        self.light_cube.draw_instanced();
+
+        self.post.finish();
+        Ok(())
+    }
+}
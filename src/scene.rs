@@ -0,0 +1,201 @@
+use crate::{
+    camera::Camera,
+    cube::Cube,
+    material::{Material, MaterialMaps, Shading},
+    model::Model,
+    texture::{ColorSpace, Texture},
+};
+use glm::ext as gle;
+use glm::{Mat4, Vec3};
+use rand::distributions::Uniform;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::{iter, rc::Rc};
+
+/// A directional light, shadowed by cascaded shadow maps.
+#[derive(Debug, Clone, Copy)]
+pub struct Sun {
+    /// Direction the light travels in, towards the scene.
+    pub direction: Vec3,
+    pub color: Vec3,
+}
+
+impl Default for Sun {
+    fn default() -> Self {
+        Self {
+            direction: glm::vec3(0.4, 1., 0.3),
+            color: glm::vec3(0.35, 0.33, 0.3),
+        }
+    }
+}
+
+pub struct Scene {
+    pub camera: Camera,
+    pub cubes: Vec<Cube>,
+    pub floor: Cube,
+    pub models: Vec<Model>,
+    pub light_positions: Vec<Vec3>,
+    pub light_colors: Vec<Vec3>,
+    pub sun: Option<Sun>,
+    pub fog_color: Vec3,
+    pub ambient_color: Vec3,
+    pub projection: Mat4,
+    /// Of the cubes and the floor, see [`Self::set_shading`].
+    shading: Shading,
+    rng: StdRng,
+}
+
+impl Scene {
+    const GRID_LIGHTS: usize = 225;
+    const CUBES: usize = 256;
+
+    pub fn new(aspect_ratio: f32) -> Self {
+        Self::with_rng(aspect_ratio, StdRng::from_entropy())
+    }
+
+    /// Same as [`Scene::new`], but every cube and light color is derived from `seed`.
+    pub fn seeded(aspect_ratio: f32, seed: u64) -> Self {
+        Self::with_rng(aspect_ratio, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(aspect_ratio: f32, mut rng: StdRng) -> Self {
+        let cubes = (0..Self::CUBES)
+            .map(|_| {
+                let material = Self::cube_material(&mut rng);
+                Cube::new(&mut rng, material)
+            })
+            .collect();
+        let light_positions = Self::light_grid();
+        let light_colors = {
+            let uniform = Uniform::new(0., glm::radians(360.));
+
+            (0..light_positions.len())
+                .map(|_| {
+                    let theta = rng.sample(uniform);
+                    let c = |offset: f32| 0.75 + 0.25 * (theta + glm::radians(offset)).sin();
+                    glm::vec3(c(0.), c(120.), c(240.))
+                })
+                .collect()
+        };
+        let fog_color = glm::vec3(0.2, 0.25, 0.3);
+
+        Self {
+            camera: Camera::new(),
+            cubes,
+            floor: Cube::floor(Self::floor_material()),
+            models: Vec::new(),
+            light_positions,
+            light_colors,
+            sun: None,
+            fog_color,
+            ambient_color: fog_color * 0.2,
+            projection: gle::perspective(glm::radians(90.), aspect_ratio, 0.1, 100.),
+            shading: Shading::default(),
+            rng,
+        }
+    }
+
+    /// A random hue, either matte, glossy (half of them metal) or glowing.
+    pub fn cube_material(rng: &mut impl Rng) -> Material {
+        let hue = rng.sample(Uniform::new(0., 360.));
+        let c = |offset: f32| 0.6 + 0.4 * glm::radians(hue + offset).sin();
+        let albedo = glm::vec3(c(0.), c(120.), c(240.));
+
+        match rng.gen_range(0..8) {
+            0 => Material {
+                albedo,
+                specular: 0.2,
+                shininess: 16.,
+                roughness: 0.6,
+                emissive: albedo * 0.6,
+                ..Default::default()
+            },
+            1..=3 => Material {
+                albedo,
+                specular: 1.,
+                shininess: 128.,
+                metallic: if rng.gen_bool(0.5) { 1. } else { 0. },
+                roughness: 0.25,
+                ..Default::default()
+            },
+            _ => Material {
+                albedo,
+                specular: 0.1,
+                shininess: 8.,
+                roughness: 0.8,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Gray tiles, two units wide.
+    fn floor_material() -> Material {
+        const SIZE: u32 = 64;
+        let texels: Vec<u8> = (0..SIZE * SIZE)
+            .flat_map(|i| {
+                let (x, y) = (i % SIZE, i / SIZE);
+                let light = (2 * x / SIZE + 2 * y / SIZE).is_multiple_of(2);
+                let c = if light { 200 } else { 120 };
+                [c, c, c, 255]
+            })
+            .collect();
+        let checker = Texture::from_rgba8(SIZE, SIZE, &texels, ColorSpace::Srgb);
+
+        Material {
+            specular: 0.3,
+            roughness: 0.7,
+            // the floor cube is 1000 units wide, each repetition covers 2 by 2 tiles
+            tiling: 250.,
+            maps: MaterialMaps {
+                diffuse: Some(Rc::new(checker)),
+                specular: None,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Shades the floor and every cube, including those spawned later, with
+    /// `shading`; models keep the shading of their own materials.
+    pub fn set_shading(&mut self, shading: Shading) {
+        self.shading = shading;
+
+        for cube in iter::once(&mut self.floor).chain(&mut self.cubes) {
+            cube.material.shading = shading;
+        }
+    }
+
+    /// 15x15 lights spaced 12 units apart, centered on the origin.
+    pub fn light_grid() -> Vec<Vec3> {
+        (0..Self::GRID_LIGHTS as i32)
+            .map(|i| {
+                let x = i / 15 - 7;
+                let z = i % 15 - 7;
+                glm::vec3((x * 12) as f32, 0., (z * 12) as f32)
+            })
+            .collect()
+    }
+
+    /// Spawns or removes cubes until there are `count` of them.
+    pub fn resize_cubes(&mut self, count: usize) {
+        let rng = &mut self.rng;
+        self.cubes.resize_with(count, || {
+            let material = Material {
+                shading: self.shading,
+                ..Self::cube_material(rng)
+            };
+            Cube::new(rng, material)
+        });
+    }
+
+    /// Advances every cube; `force_mult` pulls them towards (or pushes them away from)
+    /// the point 12 units in front of the camera.
+    pub fn update(&mut self, dt: f32, force_mult: f32) {
+        let target = self.camera.position + self.camera.front() * 12.;
+
+        for cube in &mut self.cubes {
+            let diff = target - cube.position;
+            let force = (glm::normalize(diff) / glm::length(diff)) * force_mult;
+            cube.update(dt, force, &mut self.rng);
+        }
+    }
+}
@@ -1,36 +1,149 @@
+pub mod compute_program;
+mod diagnostic;
+pub mod preprocessor;
+pub mod program_cache;
+mod reflection;
 mod shader_core;
 pub mod shader_program;
+pub mod storage_buffer;
+pub mod texture_buffer;
+pub mod uniform_buffer;
+pub mod uniforms;
 
 use gl::types::GLenum;
-use std::{error::Error, fmt::Display};
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    path::PathBuf,
+};
 
-#[derive(Debug)]
-struct ShaderError {
-    msg: String,
+/// Shader stages in pipeline order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Stage {
+    Vertex,
+    TessControl,
+    TessEvaluation,
+    Geometry,
+    Fragment,
+    Compute,
 }
 
-impl Display for ShaderError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.msg)
+impl Stage {
+    const fn gl_type(self) -> GLenum {
+        match self {
+            Self::Vertex => gl::VERTEX_SHADER,
+            Self::TessControl => gl::TESS_CONTROL_SHADER,
+            Self::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+            Self::Geometry => gl::GEOMETRY_SHADER,
+            Self::Fragment => gl::FRAGMENT_SHADER,
+            Self::Compute => gl::COMPUTE_SHADER,
+        }
     }
 }
 
-impl Error for ShaderError {}
+impl Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Vertex => "vertex",
+            Self::TessControl => "tessellation control",
+            Self::TessEvaluation => "tessellation evaluation",
+            Self::Geometry => "geometry",
+            Self::Fragment => "fragment",
+            Self::Compute => "compute",
+        };
+        write!(f, "{name}")
+    }
+}
 
-#[derive(Debug)]
-struct Vertex;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
 
-#[derive(Debug)]
-struct Fragment;
+/// One message from a driver log, located in the original sources when the
+/// driver reported a line.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub file: Option<PathBuf>,
+    pub line: Option<usize>,
+    pub message: String,
+    /// Numbered source lines around `line`.
+    pub excerpt: Vec<(usize, String)>,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "{}:{line}: ", file.display())?,
+            (Some(file), None) => write!(f, "{}: ", file.display())?,
+            (None, Some(line)) => write!(f, "line {line}: ")?,
+            (None, None) => {}
+        }
 
-trait ShaderType {
-    const TYPE: GLenum;
+        write!(f, "{severity}: {}", self.message)?;
+
+        let width = self.excerpt.last().map_or(0, |(n, _)| n.to_string().len());
+
+        for (number, text) in &self.excerpt {
+            let marker = if Some(*number) == self.line { '>' } else { ' ' };
+            write!(f, "\n{marker} {number:>width$} | {text}")?;
+        }
+
+        Ok(())
+    }
 }
 
-impl ShaderType for Vertex {
-    const TYPE: GLenum = gl::VERTEX_SHADER;
+#[derive(Debug)]
+pub enum ShaderError {
+    /// A stage failed to compile.
+    Compile {
+        stage: Stage,
+        path: PathBuf,
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// The stages compiled but did not link.
+    Link {
+        paths: Vec<PathBuf>,
+        diagnostics: Vec<Diagnostic>,
+    },
+    /// A source could not be assembled, e.g. because of an include cycle.
+    Preprocess(String),
+    /// The program does not match what Rust expects of it, e.g. a uniform block size.
+    Interface(String),
 }
 
-impl ShaderType for Fragment {
-    const TYPE: GLenum = gl::FRAGMENT_SHADER;
+impl Display for ShaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let diagnostics = match self {
+            Self::Compile {
+                stage,
+                path,
+                diagnostics,
+            } => {
+                write!(f, "could not compile {stage} shader {}", path.display())?;
+                diagnostics
+            }
+            Self::Link { paths, diagnostics } => {
+                let paths: Vec<_> = paths.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "could not link {}", paths.join(" + "))?;
+                diagnostics
+            }
+            Self::Preprocess(msg) | Self::Interface(msg) => return write!(f, "{msg}"),
+        };
+
+        for diagnostic in diagnostics {
+            write!(f, "\n{diagnostic}")?;
+        }
+
+        Ok(())
+    }
 }
+
+impl Error for ShaderError {}
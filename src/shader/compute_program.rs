@@ -0,0 +1,99 @@
+use crate::{
+    shader::{preprocessor::Defines, shader_program::ShaderProgram, Stage},
+    util,
+};
+use anyhow::{anyhow, Result};
+use gl::types::{GLbitfield, GLenum, GLuint};
+use glm::UVec3;
+use std::path::Path;
+
+/// A program with a single compute stage, read from `shaders/{filename}`. One
+/// only exists on contexts with compute shaders, which is why `bind_image` and
+/// `barrier` are methods.
+#[derive(Debug)]
+pub struct ComputeProgram {
+    program: ShaderProgram,
+}
+
+impl ComputeProgram {
+    /// Compute shaders and storage buffers are core from this GL version on.
+    pub const VERSION: (u32, u32) = (4, 3);
+
+    /// Whether the current context has compute shaders. Without them, the GL
+    /// entry points used here and by `StorageBuffer` are not loaded.
+    pub fn supported() -> bool {
+        util::gl_version() >= Self::VERSION
+    }
+
+    /// Fails unless [`Self::supported`].
+    pub(super) fn require() -> Result<()> {
+        if Self::supported() {
+            return Ok(());
+        }
+
+        let (major, minor) = util::gl_version();
+        let (need_major, need_minor) = Self::VERSION;
+        Err(anyhow!(
+            "compute shaders need OpenGL {need_major}.{need_minor}, the context is {major}.{minor}"
+        ))
+    }
+
+    pub fn new(filename: &str) -> Result<Self> {
+        Self::with_defines(filename, &Defines::new())
+    }
+
+    pub fn with_defines(filename: &str, defines: &Defines) -> Result<Self> {
+        Self::require()?;
+        let path = Path::new("shaders").join(filename);
+
+        Ok(Self {
+            program: ShaderProgram::from_stages(vec![(Stage::Compute, path)], defines.clone())?,
+        })
+    }
+
+    /// For uniforms and uniform blocks; enable it before setting uniforms.
+    pub fn program(&self) -> &ShaderProgram {
+        &self.program
+    }
+
+    pub fn program_mut(&mut self) -> &mut ShaderProgram {
+        &mut self.program
+    }
+
+    /// See [`ShaderProgram::reload`].
+    pub fn reload(&mut self) -> bool {
+        self.program.reload()
+    }
+
+    /// See [`ShaderProgram::check_uniforms`].
+    pub fn check_uniforms(&self) {
+        self.program.check_uniforms();
+    }
+
+    /// Binds level 0 of `texture` to image `unit`, e.g. for an `image2D` with a
+    /// `layout (binding = unit)` qualifier. `access` is `GL_READ_ONLY`,
+    /// `GL_WRITE_ONLY` or `GL_READ_WRITE`, `format` an internal format like `GL_RGBA16F`.
+    pub fn bind_image(&self, unit: GLuint, texture: GLuint, access: GLenum, format: GLenum) {
+        unsafe {
+            gl::BindImageTexture(unit, texture, 0, gl::FALSE, 0, access, format);
+        }
+    }
+
+    /// Runs `groups` work groups. Its writes reach later commands only after a
+    /// [`Self::barrier`] with the bits of the way they are read. Storage buffers
+    /// are bound with `StorageBuffer::bind`.
+    pub fn dispatch(&self, groups: UVec3) {
+        self.program.enable();
+
+        unsafe {
+            gl::DispatchCompute(groups.x, groups.y, groups.z);
+        }
+    }
+
+    /// `glMemoryBarrier`, e.g. with `GL_SHADER_STORAGE_BARRIER_BIT`.
+    pub fn barrier(&self, bits: GLbitfield) {
+        unsafe {
+            gl::MemoryBarrier(bits);
+        }
+    }
+}
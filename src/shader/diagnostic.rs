@@ -0,0 +1,260 @@
+//! Splits driver info logs into [`Diagnostic`]s. Every vendor has its own format:
+//! Mesa writes `0:12(5): error: msg`, NVIDIA `0(12) : error C0000: msg` and AMD
+//! `ERROR: 0:12: msg`, where `0` is the source string number.
+
+use crate::shader::{Diagnostic, Severity};
+use std::{ops::Range, path::PathBuf};
+
+/// `resolve` maps a source string number and line to a file and a numbered
+/// excerpt. Lines without a location or severity continue the previous message.
+pub fn parse(
+    log: &str,
+    resolve: impl Fn(usize, usize) -> Option<(PathBuf, Vec<(usize, String)>)>,
+) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    for text in log.lines().map(str::trim).filter(|text| !text.is_empty()) {
+        let located = location(text);
+        let (prefix, rest) = match &located {
+            Some((span, ..)) => (&text[..span.start], &text[span.end..]),
+            None => ("", text),
+        };
+        let (severity, message) = message(prefix, rest);
+
+        if let (None, None, Some(last)) = (&located, severity, diagnostics.last_mut()) {
+            last.message.push('\n');
+            last.message.push_str(text);
+            continue;
+        }
+
+        let (file, line, excerpt) = match located {
+            Some((_, file, line)) => match resolve(file, line) {
+                Some((path, excerpt)) => (Some(path), Some(line), excerpt),
+                None => (None, Some(line), Vec::new()),
+            },
+            None => (None, None, Vec::new()),
+        };
+
+        diagnostics.push(Diagnostic {
+            severity: severity.unwrap_or(Severity::Error),
+            file,
+            line,
+            message,
+            excerpt,
+        });
+    }
+
+    diagnostics
+}
+
+/// Finds the severity in the text around a location and strips everything but
+/// the message itself.
+fn message(prefix: &str, rest: &str) -> (Option<Severity>, String) {
+    let keyword = |text: &str| {
+        let lower = text.to_ascii_lowercase();
+        [("error", Severity::Error), ("warning", Severity::Warning)]
+            .into_iter()
+            .find(|(word, _)| lower.starts_with(word))
+    };
+
+    // Mesa's column
+    let rest = match rest.strip_prefix('(').and_then(|r| r.split_once(')')) {
+        Some((column, after)) if column.bytes().all(|b| b.is_ascii_digit()) => after,
+        _ => rest,
+    };
+    let rest = rest.trim_start_matches([':', ' ']);
+
+    let (severity, rest) = match keyword(rest) {
+        Some((word, severity)) => {
+            let after = rest[word.len()..].trim_start();
+
+            // NVIDIA's error code
+            let after = match after.split_once(':') {
+                Some((code, message)) if code.bytes().all(|b| b.is_ascii_alphanumeric()) => message,
+                _ => after,
+            };
+            (Some(severity), after)
+        }
+        None => (keyword(prefix.trim_start()).map(|(_, s)| s), rest),
+    };
+
+    (severity, rest.trim_start_matches([':', ' ']).to_string())
+}
+
+/// Finds the first `file:line` or `file(line)` pair of numbers in a log line,
+/// returning its byte range with the source string number and line.
+fn location(line: &str) -> Option<(Range<usize>, usize, usize)> {
+    let bytes = line.as_bytes();
+    let digits = |from: usize| {
+        let len = bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count();
+        (len > 0).then(|| (line[from..from + len].parse().ok(), from + len))
+    };
+
+    for start in 0..bytes.len() {
+        if !bytes[start].is_ascii_digit() || (start > 0 && bytes[start - 1].is_ascii_digit()) {
+            continue;
+        }
+
+        let Some((Some(file), end)) = digits(start) else {
+            continue;
+        };
+
+        match bytes.get(end) {
+            Some(b':') => {
+                if let Some((Some(number), after)) = digits(end + 1) {
+                    return Some((start..after, file, number));
+                }
+            }
+            Some(b'(') => {
+                if let Some((Some(number), after)) = digits(end + 1) {
+                    if bytes.get(after) == Some(&b')') {
+                        return Some((start..after + 1, file, number));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names source string `n` `n.glsl`, without excerpts.
+    fn parse_log(log: &str) -> Vec<Diagnostic> {
+        parse(log, |file, _| {
+            Some((format!("{file}.glsl").into(), Vec::new()))
+        })
+    }
+
+    fn summary(d: &Diagnostic) -> (Severity, Option<String>, Option<usize>, &str) {
+        let file = d.file.as_ref().map(|f| f.display().to_string());
+        (d.severity, file, d.line, &d.message)
+    }
+
+    #[test]
+    fn mesa() {
+        let log = "0:12(5): error: `foo' undeclared\n1:3(10): warning: unused variable `x'\n";
+        let diagnostics = parse_log(log);
+
+        assert_eq!(
+            diagnostics.iter().map(summary).collect::<Vec<_>>(),
+            [
+                (
+                    Severity::Error,
+                    Some("0.glsl".into()),
+                    Some(12),
+                    "`foo' undeclared"
+                ),
+                (
+                    Severity::Warning,
+                    Some("1.glsl".into()),
+                    Some(3),
+                    "unused variable `x'"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn nvidia() {
+        let log = "0(12) : error C0000: syntax error, unexpected '}' at token \"}\"\n\
+                   2(7) : warning C7050: \"x\" might be used before being initialized\n";
+        let diagnostics = parse_log(log);
+
+        assert_eq!(
+            diagnostics.iter().map(summary).collect::<Vec<_>>(),
+            [
+                (
+                    Severity::Error,
+                    Some("0.glsl".into()),
+                    Some(12),
+                    "syntax error, unexpected '}' at token \"}\""
+                ),
+                (
+                    Severity::Warning,
+                    Some("2.glsl".into()),
+                    Some(7),
+                    "\"x\" might be used before being initialized"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn amd() {
+        let log = "WARNING: 0:5: 'x' : unused variable\n\
+                   ERROR: 1:12: 'foo' : undeclared identifier\n\
+                   ERROR: 1 compilation errors.  No code generated.\n";
+        let diagnostics = parse_log(log);
+
+        assert_eq!(
+            diagnostics.iter().map(summary).collect::<Vec<_>>(),
+            [
+                (
+                    Severity::Warning,
+                    Some("0.glsl".into()),
+                    Some(5),
+                    "'x' : unused variable"
+                ),
+                (
+                    Severity::Error,
+                    Some("1.glsl".into()),
+                    Some(12),
+                    "'foo' : undeclared identifier"
+                ),
+                (
+                    Severity::Error,
+                    None,
+                    None,
+                    "1 compilation errors.  No code generated."
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn unresolved_locations_keep_their_line() {
+        let diagnostics = parse("0:4(1): error: x", |_, _| None);
+
+        assert_eq!(
+            diagnostics.iter().map(summary).collect::<Vec<_>>(),
+            [(Severity::Error, None, Some(4), "x")]
+        );
+    }
+
+    #[test]
+    fn unparseable_lines() {
+        // on its own, a line without location or severity is an error of its own
+        let diagnostics = parse_log("link failed for unknown reasons\n");
+
+        assert_eq!(
+            diagnostics.iter().map(summary).collect::<Vec<_>>(),
+            [(
+                Severity::Error,
+                None,
+                None,
+                "link failed for unknown reasons"
+            )]
+        );
+
+        // after a message, it continues that message
+        let diagnostics = parse_log("0:1(1): error: first\n  more detail\n\n");
+
+        assert_eq!(
+            diagnostics.iter().map(summary).collect::<Vec<_>>(),
+            [(
+                Severity::Error,
+                Some("0.glsl".into()),
+                Some(1),
+                "first\nmore detail"
+            )]
+        );
+    }
+}
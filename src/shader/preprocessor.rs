@@ -0,0 +1,335 @@
+//! Resolves `#include "file"` directives and injects `#define`s from Rust before
+//! sources reach the driver. Every file gets a source string number in `#line`
+//! directives, so driver logs report original lines and [`Source`] can map them
+//! back to files.
+
+use crate::shader::{diagnostic, Diagnostic, ShaderError};
+use anyhow::{Context, Result};
+use std::{
+    ffi::CString,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Macros prepended to a shader right after its `#version` line.
+#[derive(Debug, Clone, Default, Hash)]
+pub struct Defines {
+    entries: Vec<(String, String)>,
+}
+
+impl Defines {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `#define name value`.
+    pub fn define(mut self, name: &str, value: impl Display) -> Self {
+        self.entries.push((name.into(), value.to_string()));
+        self
+    }
+
+    /// Like [`Self::define`], but always with a decimal point so that GLSL reads
+    /// the value as a `float`.
+    pub fn float(self, name: &str, value: f32) -> Self {
+        self.define(name, format!("{value:?}"))
+    }
+}
+
+#[derive(Debug)]
+pub struct Source {
+    pub text: CString,
+    /// Every file read, indexed by its source string number; the root comes first.
+    pub files: Vec<PathBuf>,
+    texts: Vec<String>,
+}
+
+impl Source {
+    /// Source lines shown on each side of a diagnostic.
+    const CONTEXT: usize = 2;
+
+    pub fn load(path: &Path, defines: &Defines) -> Result<Self> {
+        let mut builder = Builder {
+            text: String::new(),
+            files: Vec::new(),
+            texts: Vec::new(),
+            stack: Vec::new(),
+            defines,
+        };
+        builder.file(path)?;
+
+        Ok(Self {
+            text: CString::new(builder.text)?,
+            files: builder.files,
+            texts: builder.texts,
+        })
+    }
+
+    /// Parses a compile log, locating each message in the file it came from.
+    pub fn diagnostics(&self, log: &str) -> Vec<Diagnostic> {
+        diagnostic::parse(log, |file, line| {
+            let path = self.files.get(file)?;
+            let lines: Vec<_> = self.texts[file].lines().collect();
+            let first = line.saturating_sub(Self::CONTEXT + 1);
+            let excerpt = (first..(line + Self::CONTEXT).min(lines.len()))
+                .map(|i| (i + 1, lines[i].to_string()))
+                .collect();
+            Some((path.clone(), excerpt))
+        })
+    }
+}
+
+struct Builder<'a> {
+    text: String,
+    files: Vec<PathBuf>,
+    texts: Vec<String>,
+    /// Files currently being expanded, for cycle detection.
+    stack: Vec<PathBuf>,
+    defines: &'a Defines,
+}
+
+impl Builder<'_> {
+    fn file(&mut self, path: &Path) -> Result<()> {
+        let canonical =
+            fs::canonicalize(path).with_context(|| format!("could not read {}", path.display()))?;
+
+        if let Some(start) = self.stack.iter().position(|p| *p == canonical) {
+            let cycle = self.stack[start..]
+                .iter()
+                .chain([&canonical])
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            Err(ShaderError::Preprocess(format!("include cycle: {cycle}")))?
+        }
+
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("could not read {}", path.display()))?;
+        let index = self.files.len();
+        let is_root = index == 0;
+        self.files.push(path.into());
+        self.texts.push(String::new());
+        self.stack.push(canonical);
+
+        if !is_root {
+            self.text.push_str(&format!("#line 1 {index}\n"));
+        }
+
+        let mut injected = false;
+
+        for (i, line) in source.lines().enumerate() {
+            let number = i + 1;
+            let directive = line.trim_start();
+
+            if is_root && !injected && directive.starts_with("#version") {
+                self.text.push_str(line);
+                self.text.push('\n');
+                self.inject(number + 1, index);
+                injected = true;
+            } else if let Some(rest) = directive.strip_prefix("#include") {
+                let err = |msg: &str| {
+                    ShaderError::Preprocess(format!("{}:{number}: {msg}", path.display()))
+                };
+                let name = rest
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|rest| rest.strip_suffix('"'))
+                    .ok_or_else(|| err("expected #include \"file\""))?;
+                let dir = path.parent().unwrap_or(Path::new(""));
+
+                self.file(&dir.join(name))
+                    .with_context(|| format!("included from {}:{number}", path.display()))?;
+                self.text
+                    .push_str(&format!("#line {} {index}\n", number + 1));
+            } else {
+                self.text.push_str(line);
+                self.text.push('\n');
+            }
+        }
+
+        // a root file without #version still gets its defines, ahead of everything
+        if is_root && !injected {
+            let body = std::mem::take(&mut self.text);
+            self.inject(1, index);
+            self.text.push_str(&body);
+        }
+
+        self.stack.pop();
+        self.texts[index] = source;
+        Ok(())
+    }
+
+    /// Emits the defines, then resumes numbering at `next` in source string `index`.
+    fn inject(&mut self, next: usize, index: usize) {
+        for (name, value) in &self.defines.entries {
+            self.text.push_str(&format!("#define {name} {value}\n"));
+        }
+
+        self.text.push_str(&format!("#line {next} {index}\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        env,
+        ops::Deref,
+        process,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    /// A directory of fixture files, removed again when dropped.
+    struct Fixture(PathBuf);
+
+    impl Deref for Fixture {
+        type Target = Path;
+
+        fn deref(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Writes `files` into a fresh directory, unique to this process and call.
+    fn files(test: &str, files: &[(&str, &str)]) -> Fixture {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        let dir = env::temp_dir().join(format!("three_d-{}-{n}-{test}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let fixture = Fixture(dir);
+
+        for (name, text) in files {
+            let path = fixture.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, text).unwrap();
+        }
+
+        fixture
+    }
+
+    #[test]
+    fn nested_includes_resolve_relative_to_their_file() {
+        let dir = files(
+            "nested",
+            &[
+                (
+                    "main.fs",
+                    "#version 330 core\n#include \"include/a.glsl\"\nvoid main() {}\n",
+                ),
+                ("include/a.glsl", "#include \"b.glsl\"\nfloat a;\n"),
+                ("include/b.glsl", "float b;\n"),
+            ],
+        );
+        let source = Source::load(&dir.join("main.fs"), &Defines::new().define("N", 4)).unwrap();
+
+        assert_eq!(
+            source.text.to_str().unwrap(),
+            "#version 330 core\n#define N 4\n#line 2 0\n\
+             #line 1 1\n#line 1 2\nfloat b;\n#line 2 1\nfloat a;\n\
+             #line 3 0\nvoid main() {}\n"
+        );
+        assert_eq!(
+            source.files,
+            [
+                dir.join("main.fs"),
+                dir.join("include/a.glsl"),
+                dir.join("include/b.glsl"),
+            ]
+        );
+    }
+
+    #[test]
+    fn defines_lead_sources_without_version() {
+        let dir = files("unversioned", &[("main.fs", "void main() {}\n")]);
+        let source = Source::load(&dir.join("main.fs"), &Defines::new().float("F", 2.)).unwrap();
+
+        assert_eq!(
+            source.text.to_str().unwrap(),
+            "#define F 2.0\n#line 1 0\nvoid main() {}\n"
+        );
+    }
+
+    #[test]
+    fn include_cycles_are_reported() {
+        let dir = files(
+            "cycle",
+            &[
+                ("main.fs", "#include \"a.glsl\"\n"),
+                ("a.glsl", "#include \"b.glsl\"\n"),
+                ("b.glsl", "#include \"a.glsl\"\n"),
+                ("self.fs", "#include \"self.fs\"\n"),
+            ],
+        );
+        let path = |name: &str| {
+            fs::canonicalize(dir.join(name))
+                .unwrap()
+                .display()
+                .to_string()
+        };
+
+        let e = Source::load(&dir.join("main.fs"), &Defines::new()).unwrap_err();
+        assert_eq!(
+            e.root_cause().to_string(),
+            format!(
+                "include cycle: {} -> {} -> {}",
+                path("a.glsl"),
+                path("b.glsl"),
+                path("a.glsl")
+            )
+        );
+
+        let e = Source::load(&dir.join("self.fs"), &Defines::new()).unwrap_err();
+        assert_eq!(
+            e.root_cause().to_string(),
+            format!("include cycle: {} -> {}", path("self.fs"), path("self.fs"))
+        );
+    }
+
+    #[test]
+    fn diagnostics_map_to_the_original_file_and_line() {
+        let dir = files(
+            "lines",
+            &[
+                (
+                    "main.fs",
+                    "#version 330 core\n#include \"a.glsl\"\nvoid main() {\n    x;\n}\n",
+                ),
+                ("a.glsl", "float a;\nfloat b = c;\n"),
+            ],
+        );
+        let source = Source::load(&dir.join("main.fs"), &Defines::new().define("N", 4)).unwrap();
+        let diagnostics = source.diagnostics(
+            "0:4(5): error: `x' undeclared\n1:2(11): warning: `c' used uninitialized\n",
+        );
+
+        assert_eq!(diagnostics.len(), 2);
+
+        let [main, included] = [&diagnostics[0], &diagnostics[1]];
+        assert_eq!(main.file, Some(dir.join("main.fs")));
+        assert_eq!(main.line, Some(4));
+        assert_eq!(main.message, "`x' undeclared");
+        assert_eq!(
+            main.excerpt,
+            [
+                (2, "#include \"a.glsl\"".to_string()),
+                (3, "void main() {".to_string()),
+                (4, "    x;".to_string()),
+                (5, "}".to_string()),
+            ]
+        );
+
+        assert_eq!(included.file, Some(dir.join("a.glsl")));
+        assert_eq!(included.line, Some(2));
+        assert_eq!(included.severity, crate::shader::Severity::Warning);
+        assert_eq!(
+            included.excerpt,
+            [(1, "float a;".to_string()), (2, "float b = c;".to_string()),]
+        );
+    }
+}
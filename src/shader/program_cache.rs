@@ -0,0 +1,245 @@
+//! Linked program binaries stored on disk with `glGetProgramBinary`, keyed by the
+//! preprocessed sources and the driver, so that unchanged programs skip
+//! compilation on the next start. Each program keeps a single file: storing a
+//! new build removes the binaries of its previous sources.
+
+use crate::shader::{
+    preprocessor::{Defines, Source},
+    Stage,
+};
+use anyhow::{anyhow, Context, Result};
+use gl::types::{GLenum, GLuint};
+use std::{
+    ffi::CStr,
+    fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    process,
+    sync::OnceLock,
+};
+
+static CACHE: OnceLock<ProgramCache> = OnceLock::new();
+
+/// Names a stored binary.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Key {
+    /// The stage files and defines, which stay the same across edits.
+    program: u64,
+    /// The driver and every preprocessed source.
+    sources: u64,
+}
+
+/// FNV-1a, unlike `DefaultHasher` stable across runs and builds.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ProgramCache {
+    dir: PathBuf,
+    /// Vendor, renderer and version; binaries never outlive a driver update.
+    driver: String,
+}
+
+impl ProgramCache {
+    const MAGIC: &[u8; 4] = b"3DPB";
+
+    /// Caches every program linked from now on in `dir`. Needs a current context
+    /// whose driver supports at least one binary format.
+    pub fn enable(dir: impl Into<PathBuf>) -> Result<()> {
+        let dir = dir.into();
+        let mut formats = 0;
+
+        unsafe {
+            gl::GetIntegerv(gl::NUM_PROGRAM_BINARY_FORMATS, &mut formats);
+        }
+
+        if formats == 0 {
+            Err(anyhow!("the driver supports no program binary formats"))?
+        }
+
+        fs::create_dir_all(&dir).with_context(|| format!("could not create {}", dir.display()))?;
+
+        let driver = [gl::VENDOR, gl::RENDERER, gl::VERSION]
+            .map(Self::string)
+            .join(" / ");
+
+        CACHE
+            .set(Self { dir, driver })
+            .map_err(|_| anyhow!("the program cache is already enabled"))
+    }
+
+    pub(super) fn get() -> Option<&'static Self> {
+        CACHE.get()
+    }
+
+    /// Identifies the program of `stages` and `defines` and the build of it from
+    /// `sources`, one per stage.
+    pub(super) fn key(
+        &self,
+        stages: &[(Stage, PathBuf)],
+        defines: &Defines,
+        sources: &[Source],
+    ) -> Key {
+        let mut hasher = Fnv::default();
+        stages.hash(&mut hasher);
+        defines.hash(&mut hasher);
+        let program = hasher.finish();
+
+        self.driver.hash(&mut hasher);
+
+        for source in sources {
+            hasher.write(source.text.as_bytes_with_nul());
+        }
+
+        Key {
+            program,
+            sources: hasher.finish(),
+        }
+    }
+
+    /// A program linked from the binary stored under `key`, if there is one the
+    /// driver still accepts.
+    pub(super) fn load(&self, key: Key) -> Option<GLuint> {
+        let path = self.path(key);
+        let data = fs::read(&path).ok()?;
+        let (format, binary) = self.parse(&data)?;
+
+        unsafe {
+            let program = gl::CreateProgram();
+            gl::ProgramBinary(program, format, binary.as_ptr() as _, binary.len() as _);
+
+            let mut success = 0;
+            gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+
+            if success == 0 {
+                gl::DeleteProgram(program);
+                // rejected, e.g. after a driver update that kept its version string
+                let _ = fs::remove_file(&path);
+                return None;
+            }
+
+            Some(program)
+        }
+    }
+
+    /// Stores `program`, which must have been linked with
+    /// `GL_PROGRAM_BINARY_RETRIEVABLE_HINT`, and removes the binaries of older
+    /// sources of the same program. The file is written under a temporary name
+    /// and renamed into place, so other instances never load it half-written.
+    /// Failures are reported, not returned, as the program works regardless.
+    pub(super) fn store(&self, key: Key, program: GLuint) {
+        let mut length = 0;
+
+        unsafe {
+            gl::GetProgramiv(program, gl::PROGRAM_BINARY_LENGTH, &mut length);
+        }
+
+        if length <= 0 {
+            return;
+        }
+
+        let mut binary = vec![0u8; length as usize];
+        let mut written = 0;
+        let mut format: GLenum = 0;
+
+        unsafe {
+            gl::GetProgramBinary(
+                program,
+                length,
+                &mut written,
+                &mut format,
+                binary.as_mut_ptr() as _,
+            );
+        }
+
+        binary.truncate(written.max(0) as usize);
+
+        let mut data = Vec::with_capacity(binary.len() + self.driver.len() + 12);
+        data.extend(Self::MAGIC);
+        data.extend(format.to_le_bytes());
+        data.extend((self.driver.len() as u32).to_le_bytes());
+        data.extend(self.driver.as_bytes());
+        data.extend(binary);
+
+        let path = self.path(key);
+        let temp = path.with_extension(format!("{}.tmp", process::id()));
+
+        if let Err(e) = fs::write(&temp, data).and_then(|()| fs::rename(&temp, &path)) {
+            let _ = fs::remove_file(&temp);
+            eprintln!("could not write {}: {e}", path.display());
+            return;
+        }
+
+        self.prune(key);
+    }
+
+    /// Removes every binary of `key`'s program but the one of its sources.
+    fn prune(&self, key: Key) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let prefix = format!("{:016x}-", key.program);
+        let current = self.path(key);
+
+        for path in entries.filter_map(|entry| Some(entry.ok()?.path())) {
+            let stale = path != current
+                && path.extension().is_some_and(|e| e == "bin")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(&prefix));
+
+            if stale {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    /// Splits a stored file into format and binary, rejecting other drivers.
+    fn parse<'a>(&self, data: &'a [u8]) -> Option<(GLenum, &'a [u8])> {
+        let rest = data.strip_prefix(Self::MAGIC)?;
+        let (format, rest) = rest.split_first_chunk::<4>()?;
+        let (length, rest) = rest.split_first_chunk::<4>()?;
+        let length = u32::from_le_bytes(*length) as usize;
+
+        if rest.get(..length)? != self.driver.as_bytes() {
+            return None;
+        }
+
+        Some((u32::from_le_bytes(*format), &rest[length..]))
+    }
+
+    fn path(&self, key: Key) -> PathBuf {
+        let Key { program, sources } = key;
+        self.dir.join(format!("{program:016x}-{sources:016x}.bin"))
+    }
+
+    fn string(name: GLenum) -> String {
+        unsafe {
+            let string = gl::GetString(name);
+
+            if string.is_null() {
+                return String::new();
+            }
+
+            CStr::from_ptr(string as _).to_string_lossy().into_owned()
+        }
+    }
+}
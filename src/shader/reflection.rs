@@ -0,0 +1,181 @@
+//! Active uniforms of a linked program, queried once with `glGetActiveUniform` so
+//! that setters neither look locations up by name nor write to the wrong type.
+
+use gl::types::{GLenum, GLint, GLuint};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeSet, HashMap},
+    ffi::{CStr, CString},
+};
+
+/// Sampler and image types, all set as `int` texture or image units.
+const SAMPLERS: &[GLenum] = &[
+    gl::SAMPLER_1D,
+    gl::SAMPLER_2D,
+    gl::SAMPLER_3D,
+    gl::SAMPLER_CUBE,
+    gl::SAMPLER_2D_SHADOW,
+    gl::SAMPLER_CUBE_SHADOW,
+    gl::SAMPLER_2D_ARRAY,
+    gl::SAMPLER_2D_ARRAY_SHADOW,
+    gl::SAMPLER_2D_MULTISAMPLE,
+    gl::SAMPLER_BUFFER,
+    gl::INT_SAMPLER_2D,
+    gl::INT_SAMPLER_BUFFER,
+    gl::UNSIGNED_INT_SAMPLER_2D,
+    gl::UNSIGNED_INT_SAMPLER_BUFFER,
+    gl::IMAGE_2D,
+    gl::IMAGE_3D,
+    gl::IMAGE_2D_ARRAY,
+];
+
+#[derive(Debug)]
+struct Uniform {
+    location: GLint,
+    ty: GLenum,
+    /// Array length, 1 for plain uniforms.
+    size: GLint,
+    set: Cell<bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct UniformTable {
+    /// The program, for messages.
+    program: String,
+    /// Arrays are listed without their `[0]` suffix.
+    table: HashMap<CString, Uniform>,
+    /// Names written to that the program does not have.
+    missing: RefCell<BTreeSet<CString>>,
+    /// Type mismatches already reported.
+    mismatched: RefCell<BTreeSet<CString>>,
+}
+
+impl UniformTable {
+    /// Lists the uniforms of `program` outside uniform blocks; `name` labels
+    /// reported problems.
+    pub fn reflect(program: GLuint, name: String) -> Self {
+        let mut count = 0;
+        let mut max_length = 0;
+
+        unsafe {
+            gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+            gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+        }
+
+        let mut table = HashMap::new();
+        let mut buf = vec![0u8; max_length.max(1) as usize];
+
+        for index in 0..count as GLuint {
+            let (mut length, mut size, mut ty) = (0, 0, 0);
+
+            let location = unsafe {
+                gl::GetActiveUniform(
+                    program,
+                    index,
+                    buf.len() as _,
+                    &mut length,
+                    &mut size,
+                    &mut ty,
+                    buf.as_mut_ptr() as _,
+                );
+                gl::GetUniformLocation(program, buf.as_ptr() as _)
+            };
+
+            // block members have no location and are checked by `bind_block`
+            if location < 0 {
+                continue;
+            }
+
+            let name = &buf[..length as usize];
+            let name = name.strip_suffix(b"[0]").unwrap_or(name);
+            let uniform = Uniform {
+                location,
+                ty,
+                size,
+                set: Cell::new(false),
+            };
+            table.insert(CString::new(name).unwrap_or_default(), uniform);
+        }
+
+        Self {
+            program: name,
+            table,
+            ..Default::default()
+        }
+    }
+
+    /// The location to write `count` values of one of the types `expected` to,
+    /// or `None` if `name` is not an active uniform of those types. Samplers and
+    /// images are accepted wherever `int` is. A mismatch is printed once; missing
+    /// names are remembered for [`Self::warnings`].
+    pub fn locate(&self, name: &CStr, expected: &[GLenum], count: usize) -> Option<GLint> {
+        let Some(uniform) = self.table.get(name) else {
+            self.missing.borrow_mut().insert(name.into());
+            return None;
+        };
+
+        let unit = expected.contains(&gl::INT) && SAMPLERS.contains(&uniform.ty);
+
+        if !(expected.contains(&uniform.ty) || unit) || count > uniform.size as usize {
+            if self.mismatched.borrow_mut().insert(name.into()) {
+                eprintln!(
+                    "{}: uniform {name:?} is {} but was set as {}",
+                    self.program,
+                    describe(uniform.ty, uniform.size as usize),
+                    describe(expected[0], count),
+                );
+            }
+
+            return None;
+        }
+
+        uniform.set.set(true);
+        Some(uniform.location)
+    }
+
+    /// Uniforms never set and names set that are not active uniforms. `None`
+    /// until anything was set, as an unused program has nothing to report yet.
+    pub fn warnings(&self) -> Option<Vec<String>> {
+        let missing = self.missing.borrow();
+
+        if missing.is_empty() && self.table.values().all(|u| !u.set.get()) {
+            return None;
+        }
+
+        let mut unset: Vec<_> = self
+            .table
+            .iter()
+            .filter(|(_, u)| !u.set.get())
+            .map(|(name, _)| format!("uniform {name:?} is never set"))
+            .collect();
+        unset.sort();
+
+        let unknown = missing.iter().map(|name| {
+            format!("uniform {name:?} is set but not active (misspelled or optimized out)")
+        });
+
+        Some(unset.into_iter().chain(unknown).collect())
+    }
+}
+
+/// `vec3` or `vec3[4]`.
+fn describe(ty: GLenum, count: usize) -> String {
+    let name = match ty {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::INT => "int",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::BOOL => "bool",
+        ty if SAMPLERS.contains(&ty) => "sampler",
+        _ => "another type",
+    };
+
+    match count {
+        1 => name.into(),
+        _ => format!("{name}[{count}]"),
+    }
+}
@@ -1,42 +1,43 @@
 use crate::{
-    shader::{ShaderError, ShaderType},
+    shader::{preprocessor::Source, ShaderError, Stage},
     util,
 };
 use anyhow::Result;
 use gl::types::GLuint;
-use std::{marker::PhantomData, ops::Deref, ptr};
+use std::{ops::Deref, path::Path, ptr};
 
 #[derive(Debug)]
-pub struct ShaderCore<T> {
+pub struct ShaderCore {
     shader: GLuint,
-    _t: PhantomData<T>,
 }
 
-impl<T: ShaderType> ShaderCore<T> {
-    pub fn new(filename: &str) -> Result<Self> {
+impl ShaderCore {
+    /// Compiles `source`, which was loaded from `path`.
+    pub fn new(stage: Stage, path: &Path, source: &Source) -> Result<Self> {
         unsafe {
-            let shader = gl::CreateShader(T::TYPE);
-            let source = util::load_file(filename)?;
-            gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+            let shader = gl::CreateShader(stage.gl_type());
+            gl::ShaderSource(shader, 1, &source.text.as_ptr(), ptr::null());
             gl::CompileShader(shader);
 
             let mut success = 0;
             gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
 
             if success == 0 {
-                let msg = util::get_log(shader, gl::GetShaderInfoLog)?;
-                Err(ShaderError { msg })?
+                let log = util::get_log(shader, gl::GetShaderiv, gl::GetShaderInfoLog);
+                gl::DeleteShader(shader);
+                Err(ShaderError::Compile {
+                    stage,
+                    path: path.into(),
+                    diagnostics: source.diagnostics(&log),
+                })?
             }
 
-            Ok(Self {
-                shader,
-                _t: Default::default(),
-            })
+            Ok(Self { shader })
         }
     }
 }
 
-impl<T> Drop for ShaderCore<T> {
+impl Drop for ShaderCore {
     fn drop(&mut self) {
         unsafe {
             gl::DeleteShader(self.shader);
@@ -44,7 +45,7 @@ impl<T> Drop for ShaderCore<T> {
     }
 }
 
-impl<T> Deref for ShaderCore<T> {
+impl Deref for ShaderCore {
     type Target = GLuint;
 
     fn deref(&self) -> &Self::Target {
@@ -1,78 +1,477 @@
 use crate::{
-    shader::{shader_core::ShaderCore, Fragment, ShaderError, Vertex},
+    shader::{
+        diagnostic,
+        preprocessor::{Defines, Source},
+        program_cache::ProgramCache,
+        reflection::UniformTable,
+        shader_core::ShaderCore,
+        uniform_buffer::UniformBlock,
+        ShaderError, Stage,
+    },
+    texture::Texture,
     util,
 };
-use anyhow::Result;
-use gl::types::{GLint, GLuint};
-use glm::{Mat4, Vec3};
-use std::ffi::CStr;
+use anyhow::{anyhow, Result};
+use gl::types::{GLenum, GLint, GLuint};
+use glm::{Mat4, UVec3, Vec2, Vec3, Vec4};
+use std::{
+    cell::{Cell, RefCell},
+    ffi::{CStr, CString},
+    fs, iter,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Re-applies a block binding after relinking, see [`ShaderProgram::bind_block`].
+type BlockBinding = fn(GLuint) -> Result<()>;
+
+/// Collects the stages of a graphics program, each read from `shaders/{filename}`.
+/// Adding a stage twice replaces the earlier file.
+#[derive(Debug, Default)]
+pub struct ProgramBuilder {
+    stages: Vec<(Stage, PathBuf)>,
+    defines: Defines,
+}
+
+impl ProgramBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn vertex(self, filename: &str) -> Self {
+        self.stage(Stage::Vertex, filename)
+    }
+
+    /// Requires a tessellation evaluation stage as well.
+    pub fn tess_control(self, filename: &str) -> Self {
+        self.stage(Stage::TessControl, filename)
+    }
+
+    pub fn tess_evaluation(self, filename: &str) -> Self {
+        self.stage(Stage::TessEvaluation, filename)
+    }
+
+    pub fn geometry(self, filename: &str) -> Self {
+        self.stage(Stage::Geometry, filename)
+    }
+
+    pub fn fragment(self, filename: &str) -> Self {
+        self.stage(Stage::Fragment, filename)
+    }
+
+    /// Injected into every stage.
+    pub fn defines(mut self, defines: &Defines) -> Self {
+        self.defines = defines.clone();
+        self
+    }
+
+    pub fn build(self) -> Result<ShaderProgram> {
+        let has = |stage| self.stages.iter().any(|(s, _)| *s == stage);
+
+        if !has(Stage::Vertex) {
+            Err(anyhow!("a graphics program needs a vertex stage"))?
+        }
+
+        if has(Stage::TessControl) && !has(Stage::TessEvaluation) {
+            Err(anyhow!(
+                "a tessellation control stage needs an evaluation stage"
+            ))?
+        }
+
+        ShaderProgram::from_stages(self.stages, self.defines)
+    }
+
+    fn stage(mut self, stage: Stage, filename: &str) -> Self {
+        let path = Path::new("shaders").join(filename);
+        self.stages.retain(|(s, _)| *s != stage);
+        self.stages.push((stage, path));
+        self.stages.sort_by_key(|(s, _)| *s);
+        self
+    }
+}
 
 #[derive(Debug)]
 pub struct ShaderProgram {
     program: GLuint,
+    stages: Vec<(Stage, PathBuf)>,
+    defines: Defines,
+    /// Every source file, includes too, with its modification time when the
+    /// program was last built.
+    stamps: Vec<(PathBuf, Option<SystemTime>)>,
+    blocks: Vec<BlockBinding>,
+    /// Sampler names by unit, offset by [`Self::TEXTURE_SLOTS`].
+    textures: RefCell<Vec<CString>>,
+    uniforms: UniformTable,
+    /// Whether [`Self::check_uniforms`] reported on this build already.
+    checked: Cell<bool>,
 }
 
 impl ShaderProgram {
     pub fn new(filename_v: &str, filename_f: &str) -> Result<Self> {
-        unsafe {
-            let dir = |filename| format!("shaders/{filename}");
+        Self::with_defines(filename_v, filename_f, &Defines::new())
+    }
+
+    /// Builds the program with `defines` injected into both stages.
+    pub fn with_defines(filename_v: &str, filename_f: &str, defines: &Defines) -> Result<Self> {
+        ProgramBuilder::new()
+            .vertex(filename_v)
+            .fragment(filename_f)
+            .defines(defines)
+            .build()
+    }
 
-            let shader_v = ShaderCore::<Vertex>::new(&dir(filename_v))?;
-            let shader_f = ShaderCore::<Fragment>::new(&dir(filename_f))?;
+    /// Compiles and links `stages` as they are; callers validate the combination.
+    pub(super) fn from_stages(stages: Vec<(Stage, PathBuf)>, defines: Defines) -> Result<Self> {
+        let (program, files) = Self::link(&stages, &defines)?;
+        let uniforms = UniformTable::reflect(program, Self::name(&stages));
 
+        Ok(Self {
+            program,
+            stages,
+            defines,
+            stamps: Self::stamps(files),
+            blocks: Vec::new(),
+            textures: RefCell::new(Vec::new()),
+            uniforms,
+            checked: Cell::new(false),
+        })
+    }
+
+    /// Rebuilds the program if one of its source files changed since the last
+    /// build. On failure the log is printed and the previous program stays in use.
+    /// Returns whether the program was replaced, which resets all uniforms.
+    pub fn reload(&mut self) -> bool {
+        let changed = self
+            .stamps
+            .iter()
+            .any(|(path, stamp)| Self::modified(path) != *stamp);
+
+        if !changed {
+            return false;
+        }
+
+        let relinked = Self::link(&self.stages, &self.defines).and_then(|(program, files)| {
+            match self.blocks.iter().try_for_each(|bind| bind(program)) {
+                Ok(()) => Ok((program, files)),
+                Err(e) => {
+                    unsafe { gl::DeleteProgram(program) };
+                    Err(e)
+                }
+            }
+        });
+
+        let name = Self::name(&self.stages);
+
+        match relinked {
+            Ok((program, files)) => {
+                unsafe { gl::DeleteProgram(self.program) };
+                self.program = program;
+                self.stamps = Self::stamps(files);
+                self.uniforms = UniformTable::reflect(program, name.clone());
+                self.checked.set(false);
+                println!("reloaded {name}");
+                true
+            }
+            Err(e) => {
+                // keep watching the same files, but only report this version once
+                for (path, stamp) in &mut self.stamps {
+                    *stamp = Self::modified(path);
+                }
+
+                eprintln!("could not reload {name}, keeping the previous program:\n{e:#}");
+                false
+            }
+        }
+    }
+
+    /// Links the stages, or loads them from the [`ProgramCache`] if it is enabled
+    /// and has them. Also returns every source file read.
+    fn link(stages: &[(Stage, PathBuf)], defines: &Defines) -> Result<(GLuint, Vec<PathBuf>)> {
+        let sources = stages
+            .iter()
+            .map(|(_, path)| Source::load(path, defines))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut files: Vec<_> = sources.iter().flat_map(|s| s.files.clone()).collect();
+        files.sort();
+        files.dedup();
+
+        let cache = ProgramCache::get().map(|cache| (cache, cache.key(stages, defines, &sources)));
+
+        if let Some(program) = cache.and_then(|(cache, key)| cache.load(key)) {
+            return Ok((program, files));
+        }
+
+        let shaders = iter::zip(stages, &sources)
+            .map(|((stage, path), source)| ShaderCore::new(*stage, path, source))
+            .collect::<Result<Vec<_>>>()?;
+
+        unsafe {
             let program = gl::CreateProgram();
 
-            gl::AttachShader(program, *shader_v);
-            gl::AttachShader(program, *shader_f);
+            if cache.is_some() {
+                gl::ProgramParameteri(program, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as _);
+            }
+
+            for shader in &shaders {
+                gl::AttachShader(program, **shader);
+            }
+
             gl::LinkProgram(program);
 
             let mut success = 0;
             gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
 
             if success == 0 {
-                let msg = util::get_log(program, gl::GetProgramInfoLog)?;
-                Err(ShaderError { msg })?
+                let log = util::get_log(program, gl::GetProgramiv, gl::GetProgramInfoLog);
+                gl::DeleteProgram(program);
+                Err(ShaderError::Link {
+                    paths: stages.iter().map(|(_, path)| path.clone()).collect(),
+                    diagnostics: diagnostic::parse(&log, |_, _| None),
+                })?
+            }
+
+            if let Some((cache, key)) = cache {
+                cache.store(key, program);
             }
 
-            Ok(Self { program })
+            Ok((program, files))
         }
     }
 
+    fn name(stages: &[(Stage, PathBuf)]) -> String {
+        let paths: Vec<_> = stages
+            .iter()
+            .map(|(_, p)| p.display().to_string())
+            .collect();
+        paths.join(" + ")
+    }
+
+    fn stamps(files: Vec<PathBuf>) -> Vec<(PathBuf, Option<SystemTime>)> {
+        files
+            .into_iter()
+            .map(|path| {
+                let stamp = Self::modified(&path);
+                (path, stamp)
+            })
+            .collect()
+    }
+
+    fn modified(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+
     pub fn enable(&self) {
         unsafe {
             gl::UseProgram(self.program);
         }
     }
 
-    pub fn set_mat4(&self, name: &CStr, mat: &Mat4) {
+    /// Prints uniforms that were never set and names set that the program does
+    /// not have, once per build and only after the program was used.
+    pub fn check_uniforms(&self) {
+        if self.checked.get() {
+            return;
+        }
+
+        if let Some(warnings) = self.uniforms.warnings() {
+            let name = Self::name(&self.stages);
+
+            for warning in warnings {
+                eprintln!("{name}: {warning}");
+            }
+
+            self.checked.set(true);
+        }
+    }
+
+    /// Connects the program's `T::NAME` block to `T::BINDING`, failing if the
+    /// block's size in GLSL does not match `T`. The binding survives reloads.
+    pub fn bind_block<T: UniformBlock>(&mut self) -> Result<()> {
+        Self::connect_block::<T>(self.program)?;
+        self.blocks.push(Self::connect_block::<T>);
+        Ok(())
+    }
+
+    fn connect_block<T: UniformBlock>(program: GLuint) -> Result<()> {
         unsafe {
-            gl::UniformMatrix4fv(
-                self.locate(name),
-                1,
-                gl::FALSE,
-                mat.as_array().as_ptr() as _,
-            )
+            let index = gl::GetUniformBlockIndex(program, T::NAME.as_ptr());
+
+            if index == gl::INVALID_INDEX {
+                Err(ShaderError::Interface(format!(
+                    "uniform block {:?} not found",
+                    T::NAME
+                )))?
+            }
+
+            let mut size = 0;
+            gl::GetActiveUniformBlockiv(program, index, gl::UNIFORM_BLOCK_DATA_SIZE, &mut size);
+
+            if size as usize != size_of::<T>() {
+                Err(ShaderError::Interface(format!(
+                    "uniform block {:?} is {size} bytes in GLSL but {} bytes in Rust",
+                    T::NAME,
+                    size_of::<T>()
+                )))?
+            }
+
+            gl::UniformBlockBinding(program, index, T::BINDING);
         }
+
+        Ok(())
+    }
+
+    /// First texture unit handed out by [`Self::set_texture`]; the units below
+    /// are bound by hand, e.g. for clusters, lights, shadow maps and the G-buffer,
+    /// except for the last, [`Texture::SCRATCH_UNIT`].
+    pub const TEXTURE_SLOTS: GLuint = 9;
+
+    /// Types `set_i32` and `set_i32_array` write; samplers are accepted too.
+    const INTS: &[GLenum] = &[gl::INT, gl::BOOL];
+
+    pub fn set_mat4(&self, name: &CStr, mat: &Mat4) {
+        let Some(location) = self.locate(name, &[gl::FLOAT_MAT4], 1) else {
+            return;
+        };
+
+        unsafe { gl::UniformMatrix4fv(location, 1, gl::FALSE, mat.as_array().as_ptr() as _) }
+    }
+
+    pub fn set_mat4_array(&self, name: &CStr, mats: &[Mat4]) {
+        let Some(location) = self.locate(name, &[gl::FLOAT_MAT4], mats.len()) else {
+            return;
+        };
+
+        unsafe { gl::UniformMatrix4fv(location, mats.len() as _, gl::FALSE, mats.as_ptr() as _) }
     }
 
     pub fn set_vec3(&self, name: &CStr, vec: Vec3) {
-        unsafe { gl::Uniform3fv(self.locate(name), 1, vec.as_array().as_ptr() as _) }
+        let Some(location) = self.locate(name, &[gl::FLOAT_VEC3], 1) else {
+            return;
+        };
+
+        unsafe { gl::Uniform3fv(location, 1, vec.as_array().as_ptr() as _) }
+    }
+
+    pub fn set_vec2(&self, name: &CStr, vec: Vec2) {
+        let Some(location) = self.locate(name, &[gl::FLOAT_VEC2], 1) else {
+            return;
+        };
+
+        unsafe { gl::Uniform2fv(location, 1, vec.as_array().as_ptr() as _) }
+    }
+
+    pub fn set_vec2_array(&self, name: &CStr, vecs: &[Vec2]) {
+        let Some(location) = self.locate(name, &[gl::FLOAT_VEC2], vecs.len()) else {
+            return;
+        };
+
+        unsafe { gl::Uniform2fv(location, vecs.len() as _, vecs.as_ptr() as _) }
+    }
+
+    pub fn set_vec4(&self, name: &CStr, vec: Vec4) {
+        let Some(location) = self.locate(name, &[gl::FLOAT_VEC4], 1) else {
+            return;
+        };
+
+        unsafe { gl::Uniform4fv(location, 1, vec.as_array().as_ptr() as _) }
+    }
+
+    pub fn set_vec4_array(&self, name: &CStr, vecs: &[Vec4]) {
+        let Some(location) = self.locate(name, &[gl::FLOAT_VEC4], vecs.len()) else {
+            return;
+        };
+
+        unsafe { gl::Uniform4fv(location, vecs.len() as _, vecs.as_ptr() as _) }
     }
 
     pub fn set_f32(&self, name: &CStr, value: f32) {
-        unsafe { gl::Uniform1fv(self.locate(name), 1, &value as _) }
+        let Some(location) = self.locate(name, &[gl::FLOAT], 1) else {
+            return;
+        };
+
+        unsafe { gl::Uniform1fv(location, 1, &value as _) }
     }
 
     pub fn set_vec3_array(&self, name: &CStr, vecs: &[Vec3]) {
-        unsafe { gl::Uniform3fv(self.locate(name), vecs.len() as _, vecs.as_ptr() as _) }
+        let Some(location) = self.locate(name, &[gl::FLOAT_VEC3], vecs.len()) else {
+            return;
+        };
+
+        unsafe { gl::Uniform3fv(location, vecs.len() as _, vecs.as_ptr() as _) }
+    }
+
+    pub fn set_f32_array(&self, name: &CStr, values: &[f32]) {
+        let Some(location) = self.locate(name, &[gl::FLOAT], values.len()) else {
+            return;
+        };
+
+        unsafe { gl::Uniform1fv(location, values.len() as _, values.as_ptr()) }
     }
 
     pub fn set_u32(&self, name: &CStr, value: u32) {
-        unsafe { gl::Uniform1ui(self.locate(name), value) }
+        let Some(location) = self.locate(name, &[gl::UNSIGNED_INT, gl::BOOL], 1) else {
+            return;
+        };
+
+        unsafe { gl::Uniform1ui(location, value) }
+    }
+
+    pub fn set_u32_array(&self, name: &CStr, values: &[u32]) {
+        let Some(location) = self.locate(name, &[gl::UNSIGNED_INT, gl::BOOL], values.len()) else {
+            return;
+        };
+
+        unsafe { gl::Uniform1uiv(location, values.len() as _, values.as_ptr()) }
+    }
+
+    pub fn set_i32(&self, name: &CStr, value: i32) {
+        let Some(location) = self.locate(name, Self::INTS, 1) else {
+            return;
+        };
+
+        unsafe { gl::Uniform1i(location, value) }
+    }
+
+    pub fn set_i32_array(&self, name: &CStr, values: &[i32]) {
+        let Some(location) = self.locate(name, Self::INTS, values.len()) else {
+            return;
+        };
+
+        unsafe { gl::Uniform1iv(location, values.len() as _, values.as_ptr()) }
+    }
+
+    pub fn set_uvec3(&self, name: &CStr, vec: UVec3) {
+        let Some(location) = self.locate(name, &[gl::UNSIGNED_INT_VEC3], 1) else {
+            return;
+        };
+
+        unsafe { gl::Uniform3ui(location, vec.x, vec.y, vec.z) }
+    }
+
+    /// Binds `texture` to the unit this program reserved for the sampler `name`
+    /// and points the sampler at it. Each name keeps its unit for the lifetime of
+    /// the program, so callers never pick units themselves.
+    pub fn set_texture(&self, name: &CStr, texture: &Texture) {
+        let slot = {
+            let mut textures = self.textures.borrow_mut();
+
+            match textures.iter().position(|n| n.as_c_str() == name) {
+                Some(slot) => slot,
+                None => {
+                    textures.push(name.into());
+                    textures.len() - 1
+                }
+            }
+        };
+
+        let unit = Self::TEXTURE_SLOTS + slot as GLuint;
+        texture.bind(unit);
+        self.set_i32(name, unit as _);
     }
 
-    fn locate(&self, name: &CStr) -> GLint {
-        unsafe { gl::GetUniformLocation(self.program, name.as_ptr()) }
+    fn locate(&self, name: &CStr, expected: &[GLenum], count: usize) -> Option<GLint> {
+        self.uniforms.locate(name, expected, count)
     }
 }
 
@@ -0,0 +1,102 @@
+use crate::{graphics::Pod, shader::compute_program::ComputeProgram};
+use anyhow::Result;
+use gl::types::GLuint;
+use std::marker::PhantomData;
+
+/// A shader storage buffer holding `T`s laid out according to std430, mirroring
+/// a GLSL `layout (std430) buffer { T items[]; };` block.
+#[derive(Debug)]
+pub struct StorageBuffer<T> {
+    ssbo: GLuint,
+    len: usize,
+    _t: PhantomData<T>,
+}
+
+impl<T: Pod> StorageBuffer<T> {
+    /// Fails unless [`ComputeProgram::supported`].
+    pub fn new(data: &[T]) -> Result<Self> {
+        ComputeProgram::require()?;
+        let mut ssbo = 0;
+
+        unsafe {
+            gl::GenBuffers(1, &mut ssbo as _);
+        }
+
+        let mut buffer = Self {
+            ssbo,
+            len: 0,
+            _t: Default::default(),
+        };
+        buffer.update(data);
+        Ok(buffer)
+    }
+
+    /// Replaces the contents, reallocating when the length changes.
+    pub fn update(&mut self, data: &[T]) {
+        unsafe {
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.ssbo);
+
+            if data.len() == self.len {
+                gl::BufferSubData(
+                    gl::SHADER_STORAGE_BUFFER,
+                    0,
+                    size_of_val(data) as _,
+                    data.as_ptr() as _,
+                );
+            } else {
+                gl::BufferData(
+                    gl::SHADER_STORAGE_BUFFER,
+                    size_of_val(data) as _,
+                    data.as_ptr() as _,
+                    gl::DYNAMIC_DRAW,
+                );
+            }
+        }
+
+        self.len = data.len();
+    }
+
+    /// Copies the contents back; shader writes must be made visible first with
+    /// `GL_BUFFER_UPDATE_BARRIER_BIT`.
+    pub fn read(&self) -> Vec<T>
+    where
+        T: Default,
+    {
+        let mut data = vec![T::default(); self.len];
+
+        unsafe {
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.ssbo);
+            gl::GetBufferSubData(
+                gl::SHADER_STORAGE_BUFFER,
+                0,
+                size_of_val(data.as_slice()) as _,
+                data.as_mut_ptr() as _,
+            );
+        }
+
+        data
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Binds the buffer to the storage block with `layout (binding = binding)`.
+    pub fn bind(&self, binding: GLuint) {
+        unsafe {
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, binding, self.ssbo);
+        }
+    }
+}
+
+impl<T> Drop for StorageBuffer<T> {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &mut self.ssbo as _);
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Buffer textures: arrays of texels in a buffer object, read in GLSL with
+//! `texelFetch` on a `samplerBuffer`, with room for far more data than a
+//! uniform block.
+
+use crate::graphics::Pod;
+use gl::types::{GLenum, GLint, GLuint};
+use std::ptr;
+
+#[derive(Debug)]
+pub struct TextureBuffer {
+    buffer: GLuint,
+    texture: GLuint,
+}
+
+impl TextureBuffer {
+    /// An empty buffer read as texels of `format`, e.g. `gl::RGBA32F`.
+    pub fn new(format: GLenum) -> Self {
+        let (mut buffer, mut texture) = (0, 0);
+
+        unsafe {
+            gl::GenBuffers(1, &mut buffer as _);
+            gl::BindBuffer(gl::TEXTURE_BUFFER, buffer);
+            gl::BufferData(gl::TEXTURE_BUFFER, 0, ptr::null(), gl::STREAM_DRAW);
+
+            gl::GenTextures(1, &mut texture as _);
+            gl::BindTexture(gl::TEXTURE_BUFFER, texture);
+            gl::TexBuffer(gl::TEXTURE_BUFFER, format, buffer);
+        }
+
+        Self { buffer, texture }
+    }
+
+    /// `GL_MAX_TEXTURE_BUFFER_SIZE`: texels addressable by `texelFetch`, at least
+    /// 65536.
+    pub fn max_texels() -> usize {
+        let mut max: GLint = 0;
+
+        unsafe {
+            gl::GetIntegerv(gl::MAX_TEXTURE_BUFFER_SIZE, &mut max);
+        }
+
+        max as _
+    }
+
+    /// Replaces the contents; meant to be called every frame.
+    pub fn upload<T: Pod>(&self, data: &[T]) {
+        unsafe {
+            gl::BindBuffer(gl::TEXTURE_BUFFER, self.buffer);
+            gl::BufferData(
+                gl::TEXTURE_BUFFER,
+                size_of_val(data) as _,
+                data.as_ptr() as _,
+                gl::STREAM_DRAW,
+            );
+        }
+    }
+
+    pub fn bind(&self, unit: GLuint) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(gl::TEXTURE_BUFFER, self.texture);
+        }
+    }
+}
+
+impl Drop for TextureBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &mut self.texture as _);
+            gl::DeleteBuffers(1, &mut self.buffer as _);
+        }
+    }
+}
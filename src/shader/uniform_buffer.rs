@@ -0,0 +1,74 @@
+use anyhow::{anyhow, Result};
+use gl::types::{GLint, GLuint};
+use std::{ffi::CStr, marker::PhantomData, ptr};
+
+/// A `#[repr(C)]` struct laid out according to std140, mirroring a GLSL
+/// `layout (std140) uniform NAME { ... };` block.
+pub trait UniformBlock: Copy {
+    const NAME: &'static CStr;
+    /// Binding point shared by every program using the block.
+    const BINDING: GLuint;
+}
+
+#[derive(Debug)]
+pub struct UniformBuffer<T> {
+    ubo: GLuint,
+    _t: PhantomData<T>,
+}
+
+impl<T: UniformBlock> UniformBuffer<T> {
+    /// Allocates the buffer and binds it to `T::BINDING`, where it stays.
+    pub fn new() -> Result<Self> {
+        let mut max_size: GLint = 0;
+        let mut ubo = 0;
+
+        unsafe {
+            gl::GetIntegerv(gl::MAX_UNIFORM_BLOCK_SIZE, &mut max_size);
+        }
+
+        if size_of::<T>() > max_size as usize {
+            Err(anyhow!(
+                "uniform block {:?} needs {} bytes, GL_MAX_UNIFORM_BLOCK_SIZE is {max_size}",
+                T::NAME,
+                size_of::<T>()
+            ))?
+        }
+
+        unsafe {
+            gl::GenBuffers(1, &mut ubo as _);
+            gl::BindBuffer(gl::UNIFORM_BUFFER, ubo);
+            gl::BufferData(
+                gl::UNIFORM_BUFFER,
+                size_of::<T>() as _,
+                ptr::null(),
+                gl::DYNAMIC_DRAW,
+            );
+            gl::BindBufferBase(gl::UNIFORM_BUFFER, T::BINDING, ubo);
+        }
+
+        Ok(Self {
+            ubo,
+            _t: Default::default(),
+        })
+    }
+
+    pub fn update(&self, data: &T) {
+        unsafe {
+            gl::BindBuffer(gl::UNIFORM_BUFFER, self.ubo);
+            gl::BufferSubData(
+                gl::UNIFORM_BUFFER,
+                0,
+                size_of::<T>() as _,
+                data as *const T as _,
+            );
+        }
+    }
+}
+
+impl<T> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteBuffers(1, &mut self.ubo as _);
+        }
+    }
+}
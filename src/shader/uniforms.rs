@@ -0,0 +1,131 @@
+//! Uniforms set from the fields of a Rust struct in one call, see
+//! [`derive@Uniforms`].
+
+use crate::{shader::shader_program::ShaderProgram, texture::Texture};
+use glm::{Mat4, UVec3, Vec2, Vec3, Vec4};
+use std::ffi::CStr;
+
+pub use three_d_derive::Uniforms;
+
+/// The state a pass sets on a program, usually derived:
+///
+/// ```ignore
+/// #[derive(Uniforms)]
+/// struct Camera {
+///     view: Mat4,
+///     projection: Mat4,
+///     view_pos: Vec3, // sets `viewPos`
+/// }
+/// ```
+pub trait Uniforms {
+    /// Sets every uniform on `program`, which must be enabled.
+    fn apply(&self, program: &ShaderProgram);
+}
+
+/// A value with a matching [`ShaderProgram`] setter; arrays set GLSL arrays and
+/// textures samplers.
+pub trait Uniform {
+    fn set(&self, program: &ShaderProgram, name: &CStr);
+}
+
+impl Uniform for f32 {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_f32(name, *self);
+    }
+}
+
+impl Uniform for i32 {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_i32(name, *self);
+    }
+}
+
+impl Uniform for u32 {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_u32(name, *self);
+    }
+}
+
+impl Uniform for bool {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_i32(name, *self as _);
+    }
+}
+
+impl Uniform for Vec2 {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_vec2(name, *self);
+    }
+}
+
+impl Uniform for Vec3 {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_vec3(name, *self);
+    }
+}
+
+impl Uniform for Vec4 {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_vec4(name, *self);
+    }
+}
+
+impl Uniform for UVec3 {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_uvec3(name, *self);
+    }
+}
+
+impl Uniform for Mat4 {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_mat4(name, self);
+    }
+}
+
+impl<const N: usize> Uniform for [f32; N] {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_f32_array(name, self);
+    }
+}
+
+impl<const N: usize> Uniform for [i32; N] {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_i32_array(name, self);
+    }
+}
+
+impl<const N: usize> Uniform for [u32; N] {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_u32_array(name, self);
+    }
+}
+
+impl<const N: usize> Uniform for [Vec2; N] {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_vec2_array(name, self);
+    }
+}
+
+impl<const N: usize> Uniform for [Vec3; N] {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_vec3_array(name, self);
+    }
+}
+
+impl<const N: usize> Uniform for [Vec4; N] {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_vec4_array(name, self);
+    }
+}
+
+impl<const N: usize> Uniform for [Mat4; N] {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_mat4_array(name, self);
+    }
+}
+
+impl Uniform for &Texture {
+    fn set(&self, program: &ShaderProgram, name: &CStr) {
+        program.set_texture(name, self);
+    }
+}
@@ -0,0 +1,353 @@
+//! Shadow maps: a distance cube map for each of a few selected point lights and,
+//! when the scene has a sun, a depth array with one cascade per slice of the view
+//! frustum. `lighting.fs` filters both with PCF.
+
+use crate::{
+    lights,
+    render_target::{Format, Framebuffer, Layers, RenderTarget, RenderTargetBuilder},
+    scene::{Scene, Sun},
+    shader::{preprocessor::Defines, shader_program::ShaderProgram, uniforms::Uniforms},
+    texture::Filter,
+};
+use anyhow::Result;
+use gl::types::GLuint;
+use glm::ext as gle;
+use glm::{Mat4, Vec3};
+use num_traits::One;
+use std::array;
+
+/// The shadow and sun state of `lighting.fs`.
+#[derive(Uniforms)]
+struct ShadowUniforms {
+    sun_shadow: i32,
+    point_shadows: [i32; ShadowMaps::MAX_POINT_LIGHTS],
+    shadow_lights: [i32; ShadowMaps::MAX_POINT_LIGHTS],
+    shadow_far: f32,
+    sun_enabled: bool,
+    sun_direction: Vec3,
+    sun_color: Vec3,
+    cascade_matrices: [Mat4; ShadowMaps::CASCADES],
+    cascade_splits: Vec3,
+}
+
+pub struct ShadowMaps {
+    /// Light-to-fragment distance over the shadow range, one cube per shadowed
+    /// light, allocated by `set_point_lights`.
+    cubes: Vec<RenderTarget>,
+    /// Depth with hardware comparison, one layer per cascade, allocated when a
+    /// sun is first rendered.
+    cascades: Option<RenderTarget>,
+    shader_point: ShaderProgram,
+    shader_directional: ShaderProgram,
+    point_lights: usize,
+    /// Light index for each used cube map, `-1` for unused ones.
+    selected: [i32; Self::MAX_POINT_LIGHTS],
+    cascade_matrices: [Mat4; Self::CASCADES],
+    /// View depth at which each cascade ends.
+    cascade_splits: [f32; Self::CASCADES],
+    sun: Option<Sun>,
+    scores: Vec<(f32, usize)>,
+}
+
+impl ShadowMaps {
+    /// `lighting.fs` has one sampling case per light, see `pointShadowDistance`.
+    pub const MAX_POINT_LIGHTS: usize = 4;
+    const CASCADES: usize = 3;
+    const CUBE_SIZE: u32 = 512;
+    const CASCADE_SIZE: u32 = 2048;
+    /// Beyond this view depth the fog hides everything and the sun is unshadowed.
+    const SUN_DISTANCE: f32 = 60.;
+    /// How far behind a cascade's bounds casters are still captured.
+    const CASTER_MARGIN: f32 = 40.;
+    /// Blend between uniform (0) and logarithmic (1) cascade splits.
+    const SPLIT_LAMBDA: f32 = 0.75;
+    const NEAR: f32 = 0.1;
+    const SUN_UNIT: GLuint = 2;
+    const CUBE_UNIT: GLuint = 3;
+
+    pub fn new(point_lights: usize) -> Result<Self> {
+        let mut shadows = Self {
+            cubes: Vec::new(),
+            cascades: None,
+            shader_point: ShaderProgram::new("shadow.vs", "shadow_point.fs")?,
+            shader_directional: ShaderProgram::new("shadow.vs", "shadow.fs")?,
+            point_lights: 0,
+            selected: [-1; Self::MAX_POINT_LIGHTS],
+            cascade_matrices: [Mat4::one(); Self::CASCADES],
+            cascade_splits: [0.; Self::CASCADES],
+            sun: None,
+            scores: Vec::new(),
+        };
+        shadows.set_point_lights(point_lights)?;
+        Ok(shadows)
+    }
+
+    /// Sizes of the uniform arrays in `lighting.fs`.
+    pub fn defines(defines: Defines) -> Defines {
+        defines
+            .define("MAX_POINT_SHADOWS", Self::MAX_POINT_LIGHTS)
+            .define("CASCADES", Self::CASCADES)
+    }
+
+    /// Sets how many point lights get a shadow, at most [`Self::MAX_POINT_LIGHTS`],
+    /// and allocates or frees their cube maps. On failure the count is unchanged.
+    pub fn set_point_lights(&mut self, count: usize) -> Result<()> {
+        let count = count.min(Self::MAX_POINT_LIGHTS);
+
+        while self.cubes.len() < count {
+            let cube = RenderTargetBuilder::new(Self::CUBE_SIZE, Self::CUBE_SIZE)
+                .depth(Format::Depth24)
+                .layers(Layers::Cube)
+                .filter(Filter::Nearest)
+                .build();
+
+            match cube {
+                Ok(cube) => self.cubes.push(cube),
+                Err(e) => {
+                    self.cubes.truncate(self.point_lights);
+                    return Err(e);
+                }
+            }
+        }
+
+        self.cubes.truncate(count);
+        self.point_lights = count;
+        Ok(())
+    }
+
+    pub fn point_lights(&self) -> usize {
+        self.point_lights
+    }
+
+    /// See [`ShaderProgram::reload`].
+    pub fn reload_shaders(&mut self) {
+        self.shader_point.reload();
+        self.shader_directional.reload();
+    }
+
+    /// See [`ShaderProgram::check_uniforms`].
+    pub fn check_uniforms(&self) {
+        self.shader_point.check_uniforms();
+        self.shader_directional.check_uniforms();
+    }
+
+    /// Renders every shadow map of `scene`; `casters` draws the shadow-casting
+    /// geometry in the vertex format of `Graphics<Lighting>` with the current program.
+    /// The framebuffer and viewport bound before the call are restored. Fails if
+    /// the cascades of a first sun cannot be allocated.
+    pub fn render(&mut self, scene: &Scene, casters: impl Fn()) -> Result<()> {
+        if scene.sun.is_some() && self.cascades.is_none() {
+            self.cascades = Some(
+                RenderTargetBuilder::new(Self::CASCADE_SIZE, Self::CASCADE_SIZE)
+                    .depth(Format::Depth24)
+                    .layers(Layers::Array(Self::CASCADES as _))
+                    .compare()
+                    .build()?,
+            );
+        }
+
+        let target = Framebuffer::current();
+
+        self.select_lights(scene);
+        self.render_points(scene, &casters);
+
+        self.sun = scene.sun;
+
+        if let Some(sun) = scene.sun {
+            self.update_cascades(scene, sun);
+            self.render_cascades(&casters);
+        }
+
+        target.bind();
+        Ok(())
+    }
+
+    /// Binds the maps and sets the shadow and sun uniforms of `lighting.fs`.
+    pub fn bind(&self, program: &ShaderProgram) {
+        if let Some(cascades) = &self.cascades {
+            cascades.bind_depth(Self::SUN_UNIT);
+        }
+
+        // every sampler needs its own unit, used or not, so that types never clash;
+        // unallocated maps are never sampled
+        let units = array::from_fn(|slot| {
+            let unit = Self::CUBE_UNIT + slot as GLuint;
+
+            if let Some(cube) = self.cubes.get(slot) {
+                cube.bind_depth(unit);
+            }
+
+            unit as _
+        });
+        let sun = self.sun.unwrap_or_default();
+        let [a, b, c] = self.cascade_splits;
+
+        ShadowUniforms {
+            sun_shadow: Self::SUN_UNIT as _,
+            point_shadows: units,
+            shadow_lights: self.selected,
+            shadow_far: lights::radius(),
+            sun_enabled: self.sun.is_some(),
+            sun_direction: glm::normalize(sun.direction),
+            sun_color: sun.color,
+            cascade_matrices: self.cascade_matrices,
+            cascade_splits: glm::vec3(a, b, c),
+        }
+        .apply(program);
+    }
+
+    /// Picks the lights that contribute the most at the camera: bright and near.
+    fn select_lights(&mut self, scene: &Scene) {
+        let eye = scene.camera.position;
+        let luminance = glm::vec3(0.2126, 0.7152, 0.0722);
+
+        self.scores.clear();
+        self.scores.extend(
+            scene
+                .light_positions
+                .iter()
+                .zip(&scene.light_colors)
+                .enumerate()
+                .map(|(i, (&p, &c))| {
+                    let falloff = lights::LIGHT_DECAY.powf(-glm::length(p - eye));
+                    (glm::dot(c, luminance) * falloff, i)
+                }),
+        );
+        self.scores.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        self.selected = [-1; Self::MAX_POINT_LIGHTS];
+
+        for (slot, &(_, light)) in self.scores.iter().take(self.point_lights).enumerate() {
+            self.selected[slot] = light as _;
+        }
+    }
+
+    fn render_points(&self, scene: &Scene, casters: &impl Fn()) {
+        // look directions and up vectors in the order of the cube map faces
+        const FACES: [([f32; 3], [f32; 3]); 6] = [
+            ([1., 0., 0.], [0., -1., 0.]),
+            ([-1., 0., 0.], [0., -1., 0.]),
+            ([0., 1., 0.], [0., 0., 1.]),
+            ([0., -1., 0.], [0., 0., -1.]),
+            ([0., 0., 1.], [0., -1., 0.]),
+            ([0., 0., -1.], [0., -1., 0.]),
+        ];
+
+        let far = lights::radius();
+        let projection = gle::perspective(glm::radians(90.), 1., Self::NEAR, far);
+        let v = |[x, y, z]: [f32; 3]| glm::vec3(x, y, z);
+
+        let shader = &self.shader_point;
+        shader.enable();
+        shader.set_f32(c"far", far);
+
+        for (cube, &light) in self.cubes.iter().zip(&self.selected) {
+            let Ok(light) = usize::try_from(light) else {
+                continue;
+            };
+            let position = scene.light_positions[light];
+            shader.set_vec3(c"lightPos", position);
+
+            for (face, (front, up)) in FACES.into_iter().enumerate() {
+                let view = gle::look_at(position, position + v(front), v(up));
+                shader.set_mat4(c"lightSpace", &(projection * view));
+
+                cube.bind_layer(face as _);
+
+                unsafe {
+                    gl::Clear(gl::DEPTH_BUFFER_BIT);
+                }
+
+                casters();
+            }
+        }
+    }
+
+    /// Fits a sphere around each slice of the view frustum, so that cascades keep
+    /// their size while the camera turns, and snaps it to whole shadow map texels.
+    fn update_cascades(&mut self, scene: &Scene, sun: Sun) {
+        let projection = &scene.projection;
+        let (sx, sy) = (projection[0].x, projection[1].y);
+        let (p22, p32) = (projection[2].z, projection[3].z);
+        let near = p32 / (p22 - 1.);
+        let far = (p32 / (p22 + 1.)).min(Self::SUN_DISTANCE);
+
+        // squared distance from the axis to a frustum corner, per unit of depth
+        let k2 = 1. / (sx * sx) + 1. / (sy * sy);
+        let direction = glm::normalize(sun.direction);
+        let up = if direction.x.abs() < 0.9 {
+            glm::vec3(1., 0., 0.)
+        } else {
+            glm::vec3(0., 0., 1.)
+        };
+
+        let mut start = near;
+
+        for i in 0..Self::CASCADES {
+            let f = (i + 1) as f32 / Self::CASCADES as f32;
+            let log = near * (far / near).powf(f);
+            let uniform = near + (far - near) * f;
+            let end = Self::SPLIT_LAMBDA * log + (1. - Self::SPLIT_LAMBDA) * uniform;
+
+            let center = ((start + end) * (1. + k2) / 2.).min(end);
+            let radius = ((end - center).powi(2) + end * end * k2).sqrt().ceil();
+            let center = scene.camera.position + scene.camera.front() * center;
+
+            let eye = center - direction * (radius + Self::CASTER_MARGIN);
+            let view = gle::look_at(eye, center, up);
+            let ortho = orthographic(radius, 0., 2. * radius + Self::CASTER_MARGIN);
+            let mut matrix = ortho * view;
+
+            // moving the camera only ever shifts the map by whole texels
+            let half = Self::CASCADE_SIZE as f32 / 2.;
+            let origin = matrix * glm::vec4(0., 0., 0., 1.);
+            matrix[3].x += ((origin.x * half).round() - origin.x * half) / half;
+            matrix[3].y += ((origin.y * half).round() - origin.y * half) / half;
+
+            self.cascade_matrices[i] = matrix;
+            self.cascade_splits[i] = end;
+            start = end;
+        }
+    }
+
+    fn render_cascades(&self, casters: &impl Fn()) {
+        let Some(cascades) = &self.cascades else {
+            return;
+        };
+        let shader = &self.shader_directional;
+        shader.enable();
+
+        unsafe {
+            gl::Enable(gl::POLYGON_OFFSET_FILL);
+            gl::PolygonOffset(2., 4.);
+        }
+
+        for (layer, matrix) in self.cascade_matrices.iter().enumerate() {
+            shader.set_mat4(c"lightSpace", matrix);
+
+            cascades.bind_layer(layer as _);
+
+            unsafe {
+                gl::Clear(gl::DEPTH_BUFFER_BIT);
+            }
+
+            casters();
+        }
+
+        unsafe {
+            gl::Disable(gl::POLYGON_OFFSET_FILL);
+        }
+    }
+}
+
+/// Symmetric orthographic projection of a `half_extent` square looking down -z.
+fn orthographic(half_extent: f32, near: f32, far: f32) -> Mat4 {
+    let depth = far - near;
+
+    glm::Matrix4::new(
+        glm::vec4(1. / half_extent, 0., 0., 0.),
+        glm::vec4(0., 1. / half_extent, 0., 0.),
+        glm::vec4(0., 0., -2. / depth, 0.),
+        glm::vec4(0., 0., -(far + near) / depth, 1.),
+    )
+}
@@ -0,0 +1,448 @@
+//! Color textures loaded from PNG, JPEG or Radiance HDR files: 2D, cube maps, 2D
+//! arrays and 3D color lookup tables, all mipmapped.
+
+use crate::shader::shader_program::ShaderProgram;
+use anyhow::{anyhow, Context, Result};
+use gl::types::{GLenum, GLint, GLuint};
+use std::{ffi::c_void, path::Path, ptr};
+
+/// How 8-bit texels are interpreted. HDR files are always linear.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// Colors as authored, e.g. diffuse maps; decoded to linear when sampled.
+    Srgb,
+    /// Data such as specular or normal maps.
+    Linear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+    /// Linear within and between mipmap levels.
+    Trilinear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+/// Sampler parameters stored with a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampler {
+    /// Used for minification; magnification never uses mipmaps.
+    pub filter: Filter,
+    pub wrap: Wrap,
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self {
+            filter: Filter::Trilinear,
+            wrap: Wrap::Repeat,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Pixels {
+    Rgba8(Vec<u8>),
+    Rgb32F(Vec<f32>),
+}
+
+#[derive(Debug)]
+struct Image {
+    width: u32,
+    height: u32,
+    pixels: Pixels,
+}
+
+impl Image {
+    /// Decodes by extension; `flip` puts the bottom row first, as `glTexImage2D`
+    /// expects for 2D textures.
+    fn load(path: &Path, flip: bool) -> Result<Self> {
+        let image =
+            image::open(path).with_context(|| format!("could not load {}", path.display()))?;
+        let image = if flip { image.flipv() } else { image };
+        let (width, height) = (image.width(), image.height());
+
+        let pixels = match image {
+            image::DynamicImage::ImageRgb32F(_) | image::DynamicImage::ImageRgba32F(_) => {
+                Pixels::Rgb32F(image.into_rgb32f().into_raw())
+            }
+            _ => Pixels::Rgba8(image.into_rgba8().into_raw()),
+        };
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Internal format, format and type for `glTexImage*`.
+    fn formats(&self, space: ColorSpace) -> (GLenum, GLenum, GLenum) {
+        match (&self.pixels, space) {
+            (Pixels::Rgba8(_), ColorSpace::Srgb) => (gl::SRGB8_ALPHA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            (Pixels::Rgba8(_), ColorSpace::Linear) => (gl::RGBA8, gl::RGBA, gl::UNSIGNED_BYTE),
+            (Pixels::Rgb32F(_), _) => (gl::RGB16F, gl::RGB, gl::FLOAT),
+        }
+    }
+
+    fn data(&self) -> *const c_void {
+        match &self.pixels {
+            Pixels::Rgba8(data) => data.as_ptr() as _,
+            Pixels::Rgb32F(data) => data.as_ptr() as _,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Texture {
+    texture: GLuint,
+    target: GLenum,
+    width: u32,
+    height: u32,
+    layers: u32,
+}
+
+impl Texture {
+    /// The unit textures are bound to while being created or changed, reserved
+    /// for that so that no binding used for drawing is disturbed.
+    pub const SCRATCH_UNIT: GLuint = ShaderProgram::TEXTURE_SLOTS - 1;
+
+    /// A 2D texture whose first row is the bottom of the image.
+    pub fn load(path: impl AsRef<Path>, space: ColorSpace) -> Result<Self> {
+        Ok(Self::from_image(Image::load(path.as_ref(), true)?, space))
+    }
+
+    /// A 2D texture from tightly packed RGBA8 rows, bottom row first, e.g. a
+    /// single texel standing in for a missing map.
+    pub fn from_rgba8(width: u32, height: u32, pixels: &[u8], space: ColorSpace) -> Self {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+
+        let image = Image {
+            width,
+            height,
+            pixels: Pixels::Rgba8(pixels.into()),
+        };
+        Self::from_image(image, space)
+    }
+
+    /// A cube map from square faces in the order +X, -X, +Y, -Y, +Z, -Z, each
+    /// top row first as cube maps expect.
+    pub fn cube(faces: [impl AsRef<Path>; 6], space: ColorSpace) -> Result<Self> {
+        let images = faces
+            .iter()
+            .map(|face| Image::load(face.as_ref(), false))
+            .collect::<Result<Vec<_>>>()?;
+        let first = &images[0];
+
+        if first.width != first.height {
+            Err(anyhow!("cube map faces must be square"))?
+        }
+
+        Self::check_layers(&images)?;
+
+        let texture = Self::generate(gl::TEXTURE_CUBE_MAP, first, 6);
+
+        texture.edit(|| {
+            for (face, image) in images.iter().enumerate() {
+                let (internal, format, ty) = image.formats(space);
+
+                unsafe {
+                    gl::TexImage2D(
+                        gl::TEXTURE_CUBE_MAP_POSITIVE_X + face as GLenum,
+                        0,
+                        internal as _,
+                        image.width as _,
+                        image.height as _,
+                        0,
+                        format,
+                        ty,
+                        image.data(),
+                    );
+                }
+            }
+        });
+
+        Ok(texture.finish(Sampler {
+            wrap: Wrap::ClampToEdge,
+            ..Default::default()
+        }))
+    }
+
+    /// A 2D array with one layer per file, all of the same size and kind.
+    pub fn array(layers: &[impl AsRef<Path>], space: ColorSpace) -> Result<Self> {
+        let images = layers
+            .iter()
+            .map(|layer| Image::load(layer.as_ref(), true))
+            .collect::<Result<Vec<_>>>()?;
+        let first = images
+            .first()
+            .ok_or(anyhow!("a texture array needs at least one layer"))?;
+
+        Self::check_layers(&images)?;
+
+        let texture = Self::generate(gl::TEXTURE_2D_ARRAY, first, images.len() as _);
+        let (internal, format, ty) = first.formats(space);
+
+        texture.edit(|| unsafe {
+            gl::TexImage3D(
+                gl::TEXTURE_2D_ARRAY,
+                0,
+                internal as _,
+                first.width as _,
+                first.height as _,
+                images.len() as _,
+                0,
+                format,
+                ty,
+                ptr::null(),
+            );
+
+            for (layer, image) in images.iter().enumerate() {
+                gl::TexSubImage3D(
+                    gl::TEXTURE_2D_ARRAY,
+                    0,
+                    0,
+                    0,
+                    layer as _,
+                    image.width as _,
+                    image.height as _,
+                    1,
+                    format,
+                    ty,
+                    image.data(),
+                );
+            }
+        });
+
+        Ok(texture.finish(Sampler::default()))
+    }
+
+    /// A 3D color lookup table from an 8-bit strip of `size` squares of `size` by
+    /// `size` texels side by side: red grows to the right within a square, green
+    /// downwards and blue from square to square. Linear, filtered and clamped.
+    pub fn lut(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let image = Image::load(path, false)?;
+        let size = image.height;
+
+        let Pixels::Rgba8(strip) = &image.pixels else {
+            Err(anyhow!("{}: lookup tables must be 8-bit", path.display()))?
+        };
+
+        if image.width != size * size {
+            Err(anyhow!(
+                "{}: a lookup table of size {size} must be {} texels wide",
+                path.display(),
+                size * size
+            ))?
+        }
+
+        let n = size as usize;
+        let mut pixels = Vec::with_capacity(strip.len());
+
+        for b in 0..n {
+            for g in 0..n {
+                let start = (g * n * n + b * n) * 4;
+                pixels.extend_from_slice(&strip[start..start + n * 4]);
+            }
+        }
+
+        Ok(Self::from_lut(size, pixels))
+    }
+
+    /// A lookup table of `size` cubed texels mapping every color to itself.
+    pub fn identity_lut(size: u32) -> Self {
+        let level = |i: u32| (i * 255 / (size - 1).max(1)) as u8;
+        let pixels = (0..size * size * size)
+            .flat_map(|i| {
+                let (r, g, b) = (i % size, i / size % size, i / (size * size));
+                [level(r), level(g), level(b), 255]
+            })
+            .collect();
+
+        Self::from_lut(size, pixels)
+    }
+
+    /// Replaces the default sampler: trilinear and repeating, clamped for cube maps.
+    pub fn with_sampler(self, sampler: Sampler) -> Self {
+        self.edit(|| self.apply(sampler));
+        self
+    }
+
+    pub fn bind(&self, unit: GLuint) {
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0 + unit);
+            gl::BindTexture(self.target, self.texture);
+        }
+    }
+
+    pub fn target(&self) -> GLenum {
+        self.target
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 6 for cube maps, the depth of lookup tables.
+    pub fn layers(&self) -> u32 {
+        self.layers
+    }
+
+    fn from_image(image: Image, space: ColorSpace) -> Self {
+        let texture = Self::generate(gl::TEXTURE_2D, &image, 1);
+        let (internal, format, ty) = image.formats(space);
+
+        texture.edit(|| unsafe {
+            gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                internal as _,
+                image.width as _,
+                image.height as _,
+                0,
+                format,
+                ty,
+                image.data(),
+            );
+        });
+
+        texture.finish(Sampler::default())
+    }
+
+    /// `pixels` are RGBA8 with red varying fastest, then green, then blue.
+    fn from_lut(size: u32, pixels: Vec<u8>) -> Self {
+        let image = Image {
+            width: size,
+            height: size,
+            pixels: Pixels::Rgba8(pixels),
+        };
+        let texture = Self::generate(gl::TEXTURE_3D, &image, size);
+
+        texture.edit(|| unsafe {
+            gl::TexImage3D(
+                gl::TEXTURE_3D,
+                0,
+                gl::RGBA8 as _,
+                size as _,
+                size as _,
+                size as _,
+                0,
+                gl::RGBA,
+                gl::UNSIGNED_BYTE,
+                image.data(),
+            );
+        });
+
+        texture.finish(Sampler {
+            filter: Filter::Linear,
+            wrap: Wrap::ClampToEdge,
+        })
+    }
+
+    fn check_layers(images: &[Image]) -> Result<()> {
+        let first = &images[0];
+        let mismatch = images.iter().any(|image| {
+            (image.width, image.height) != (first.width, first.height)
+                || matches!(image.pixels, Pixels::Rgba8(_))
+                    != matches!(first.pixels, Pixels::Rgba8(_))
+        });
+
+        if mismatch {
+            Err(anyhow!(
+                "all layers must have the same size and pixel format"
+            ))?
+        }
+
+        Ok(())
+    }
+
+    /// Creates the texture object; its storage is uploaded within [`Self::edit`].
+    fn generate(target: GLenum, image: &Image, layers: u32) -> Self {
+        let mut texture = 0;
+
+        unsafe {
+            gl::GenTextures(1, &mut texture);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+        }
+
+        Self {
+            texture,
+            target,
+            width: image.width,
+            height: image.height,
+            layers,
+        }
+    }
+
+    /// Generates the mipmaps of the uploaded base level.
+    fn finish(self, sampler: Sampler) -> Self {
+        self.edit(|| {
+            unsafe {
+                gl::GenerateMipmap(self.target);
+            }
+
+            self.apply(sampler);
+        });
+        self
+    }
+
+    /// Runs `edit` with the texture bound to [`Self::SCRATCH_UNIT`], leaving the
+    /// units bound for drawing and the active unit as they were.
+    fn edit(&self, edit: impl FnOnce()) {
+        let mut active: GLint = 0;
+
+        unsafe {
+            gl::GetIntegerv(gl::ACTIVE_TEXTURE, &mut active);
+        }
+
+        self.bind(Self::SCRATCH_UNIT);
+        edit();
+
+        unsafe {
+            gl::ActiveTexture(active as _);
+        }
+    }
+
+    /// Needs a bound texture object.
+    fn apply(&self, sampler: Sampler) {
+        let (min, mag) = match sampler.filter {
+            Filter::Nearest => (gl::NEAREST, gl::NEAREST),
+            Filter::Linear => (gl::LINEAR, gl::LINEAR),
+            Filter::Trilinear => (gl::LINEAR_MIPMAP_LINEAR, gl::LINEAR),
+        };
+        let wrap = match sampler.wrap {
+            Wrap::Repeat => gl::REPEAT,
+            Wrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+            Wrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+        };
+
+        unsafe {
+            gl::TexParameteri(self.target, gl::TEXTURE_MIN_FILTER, min as _);
+            gl::TexParameteri(self.target, gl::TEXTURE_MAG_FILTER, mag as _);
+
+            for axis in [gl::TEXTURE_WRAP_S, gl::TEXTURE_WRAP_T, gl::TEXTURE_WRAP_R] {
+                gl::TexParameteri(self.target, axis, wrap as _);
+            }
+        }
+    }
+}
+
+impl Drop for Texture {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteTextures(1, &self.texture);
+        }
+    }
+}
@@ -1,7 +1,11 @@
-use anyhow::Result;
-use gl::types::{GLchar, GLsizei, GLuint};
+use anyhow::{anyhow, Result};
+use gl::types::{GLchar, GLenum, GLint, GLsizei, GLuint};
 use glm::Vec3;
-use std::{ffi::CString, fs::File, io::Read};
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::Path,
+};
 
 pub fn find_sdl_gl_driver() -> Option<u32> {
     sdl2::render::drivers()
@@ -10,27 +14,90 @@ pub fn find_sdl_gl_driver() -> Option<u32> {
         .map(|(index, _)| index as _)
 }
 
+/// Major and minor version of the current context.
+pub fn gl_version() -> (u32, u32) {
+    let (mut major, mut minor) = (0, 0);
+
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+
+    (major as _, minor as _)
+}
+
+/// Reads a shader or program info log in full, replacing invalid UTF-8.
 pub fn get_log(
-    target: u32,
-    f: unsafe fn(GLuint, GLsizei, *mut GLsizei, *mut GLchar),
-) -> Result<String> {
-    let mut buf = [0; 1024];
+    target: GLuint,
+    get_iv: unsafe fn(GLuint, GLenum, *mut GLint),
+    get_log: unsafe fn(GLuint, GLsizei, *mut GLsizei, *mut GLchar),
+) -> String {
+    let mut capacity = 0;
+
+    unsafe {
+        get_iv(target, gl::INFO_LOG_LENGTH, &mut capacity);
+    }
+
+    let mut buf = vec![0u8; capacity.max(1) as usize];
     let mut length = 0;
 
     unsafe {
-        f(target, buf.len() as _, &mut length, buf.as_mut_ptr());
+        get_log(target, buf.len() as _, &mut length, buf.as_mut_ptr() as _);
     }
 
-    Ok(
-        CString::from_vec_with_nul(buf[0..=length as _].iter().map(|x| *x as _).collect())?
-            .into_string()?,
-    )
+    buf.truncate(length.max(0) as usize);
+    String::from_utf8_lossy(&buf).into_owned()
 }
 
-pub fn load_file(filename: &str) -> Result<CString> {
-    let mut buf = String::new();
-    File::open(filename)?.read_to_string(&mut buf)?;
-    Ok(CString::new(buf)?)
+/// Reads the color buffer selected with `glReadBuffer` of the bound read
+/// framebuffer as 8-bit RGBA, top row first.
+pub fn read_pixels(width: u32, height: u32) -> Vec<u8> {
+    let row = width as usize * 4;
+    let mut pixels = vec![0; row * height as usize];
+
+    unsafe {
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(
+            0,
+            0,
+            width as _,
+            height as _,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixels.as_mut_ptr() as _,
+        );
+    }
+
+    // OpenGL stores the bottom row first
+    pixels.chunks_exact(row).rev().flatten().copied().collect()
+}
+
+pub fn save_png(path: impl AsRef<Path>, width: u32, height: u32, rgba: &[u8]) -> Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()?.write_image_data(rgba)?;
+    Ok(())
+}
+
+/// Loads an 8-bit RGBA PNG as returned by [`save_png`], top row first.
+pub fn load_png(path: impl AsRef<Path>) -> Result<(u32, u32, Vec<u8>)> {
+    let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+    let mut reader = decoder.read_info()?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf)?;
+
+    if (info.color_type, info.bit_depth) != (png::ColorType::Rgba, png::BitDepth::Eight) {
+        Err(anyhow!(
+            "expected 8-bit RGBA, found {:?} {:?}",
+            info.color_type,
+            info.bit_depth
+        ))?
+    }
+
+    buf.truncate(info.buffer_size());
+    Ok((info.width, info.height, buf))
 }
 
 pub fn sphere(theta: f32, phi: f32) -> Vec3 {
@@ -0,0 +1,218 @@
+//! Golden-image tests for the lighting pipeline.
+//!
+//! Every case renders a fixed scene offscreen and compares it with
+//! `tests/golden/<name>.png`. A missing reference fails the case; set
+//! `UPDATE_GOLDEN=1` to write new references or re-bless all of them after an
+//! intended change. On failure the rendered image (and on mismatch a diff image)
+//! is written to the target tmpdir. Without a GL context the run fails, unless
+//! `GOLDEN_SKIP=1` explicitly skips it. Runs on the main thread
+//! (`harness = false`) because SDL must.
+
+use std::{env, fs, path::Path, process::ExitCode};
+use three_d::{
+    handler::Handler,
+    material::Shading,
+    render_target::{Format, RenderTargetBuilder},
+    renderer::{Pipeline, Renderer},
+    scene::Scene,
+    util,
+};
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 180;
+/// Largest per-channel difference for a pixel to still count as equal.
+const TOLERANCE: u8 = 3;
+/// Fraction of pixels allowed to exceed [`TOLERANCE`], absorbing rasterizer differences.
+const MAX_MISMATCH: f64 = 0.001;
+
+struct Case {
+    name: &'static str,
+    seed: u64,
+    steps: u32,
+    shading: Shading,
+    setup: fn(&mut Scene),
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "cube_field",
+        seed: 1,
+        steps: 90,
+        shading: Shading::Classic,
+        setup: |scene| {
+            scene.camera.position = glm::vec3(0., -6., -20.);
+            scene.camera.theta = glm::radians(15.);
+            scene.camera.phi = glm::radians(90.);
+        },
+    },
+    Case {
+        name: "single_light",
+        seed: 2,
+        steps: 30,
+        shading: Shading::Classic,
+        setup: |scene| {
+            scene.light_positions = vec![glm::vec3(0., -3., 0.)];
+            scene.light_colors = vec![glm::vec3(1., 0.2, 0.2)];
+            scene.camera.position = glm::vec3(0., -4., -10.);
+            scene.camera.theta = glm::radians(10.);
+            scene.camera.phi = glm::radians(90.);
+        },
+    },
+    Case {
+        name: "floor_grid",
+        seed: 3,
+        steps: 0,
+        shading: Shading::Classic,
+        setup: |scene| {
+            scene.cubes.clear();
+            scene.light_colors = vec![glm::vec3(1., 1., 1.); scene.light_positions.len()];
+            scene.camera.position = glm::vec3(0., -40., 0.);
+            scene.camera.theta = glm::radians(89.);
+        },
+    },
+    Case {
+        name: "cube_field_pbr",
+        seed: 1,
+        steps: 90,
+        shading: Shading::Pbr,
+        setup: |scene| {
+            scene.camera.position = glm::vec3(0., -6., -20.);
+            scene.camera.theta = glm::radians(15.);
+            scene.camera.phi = glm::radians(90.);
+        },
+    },
+];
+
+fn main() -> ExitCode {
+    let _handler = match Handler::headless(WIDTH, HEIGHT) {
+        Ok(handler) => handler,
+        Err(e) if env::var_os("GOLDEN_SKIP").is_some() => {
+            println!("golden tests SKIPPED (GOLDEN_SKIP is set), no GL context: {e}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("could not obtain headless handler: {e}");
+            eprintln!("golden tests need a GL context; set GOLDEN_SKIP=1 to skip them");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let target = RenderTargetBuilder::new(WIDTH, HEIGHT)
+        .color(Format::Rgba8)
+        .depth(Format::Depth24)
+        .build()
+        .expect("could not obtain render target");
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut failures = 0;
+
+    for case in CASES {
+        let mut renderer =
+            Renderer::new(Pipeline::Forward, 1, WIDTH, HEIGHT).expect("could not obtain renderer");
+        let mut scene = Scene::seeded(WIDTH as f32 / HEIGHT as f32, case.seed);
+        scene.set_shading(case.shading);
+        (case.setup)(&mut scene);
+
+        for _ in 0..case.steps {
+            scene.update(Handler::FIXED_DELTA, 0.);
+        }
+
+        target.bind();
+        renderer.draw(&scene, 0.).expect("could not draw frame");
+        let actual = target.read_pixels().expect("could not read back frame");
+
+        match check(case.name, &actual, update) {
+            Ok(status) => println!("golden {} ... {status}", case.name),
+            Err(e) => {
+                println!("golden {} ... FAILED\n    {e}", case.name);
+                failures += 1;
+            }
+        }
+    }
+
+    println!("\n{} passed; {failures} failed", CASES.len() - failures);
+
+    if failures == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
+
+fn check(name: &str, actual: &[u8], update: bool) -> Result<&'static str, String> {
+    let reference = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.png"));
+
+    if update {
+        fs::create_dir_all(reference.parent().unwrap_or(Path::new(".")))
+            .map_err(|e| e.to_string())?;
+        util::save_png(&reference, WIDTH, HEIGHT, actual).map_err(|e| e.to_string())?;
+        return Ok("blessed");
+    }
+
+    if !reference.exists() {
+        return Err(format!(
+            "no reference {}, run with UPDATE_GOLDEN=1 to create it\n    actual: {}",
+            reference.display(),
+            write_output(name, "actual", actual),
+        ));
+    }
+
+    let (width, height, expected) = util::load_png(&reference).map_err(|e| e.to_string())?;
+
+    if (width, height) != (WIDTH, HEIGHT) {
+        return Err(format!(
+            "reference is {width}x{height}, rendered {WIDTH}x{HEIGHT}"
+        ));
+    }
+
+    let (mismatched, diff) = compare(&expected, actual);
+    let ratio = mismatched as f64 / (WIDTH * HEIGHT) as f64;
+
+    if ratio <= MAX_MISMATCH {
+        return Ok("ok");
+    }
+
+    Err(format!(
+        "{mismatched} pixels ({:.3}%) differ by more than {TOLERANCE}\n    actual: {}\n    diff: {}",
+        ratio * 100.,
+        write_output(name, "actual", actual),
+        write_output(name, "diff", &diff),
+    ))
+}
+
+/// Writes `<name>.<suffix>.png` to the target tmpdir, returning its path or why
+/// it could not be written.
+fn write_output(name: &str, suffix: &str, rgba: &[u8]) -> String {
+    let out = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    let path = out.join(format!("{name}.{suffix}.png"));
+
+    fs::create_dir_all(&out)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| util::save_png(&path, WIDTH, HEIGHT, rgba))
+        .map(|_| path.display().to_string())
+        .unwrap_or_else(|e| format!("<could not write: {e}>"))
+}
+
+/// Counts pixels differing by more than [`TOLERANCE`] in any channel and builds a
+/// diff image: mismatches in red, everything else as a dimmed grayscale of `expected`.
+fn compare(expected: &[u8], actual: &[u8]) -> (usize, Vec<u8>) {
+    let mut mismatched = 0;
+    let diff = expected
+        .chunks_exact(4)
+        .zip(actual.chunks_exact(4))
+        .flat_map(|(e, a)| {
+            let over = e.iter().zip(a).any(|(&e, &a)| e.abs_diff(a) > TOLERANCE);
+
+            if over {
+                mismatched += 1;
+                [255, 0, 0, 255]
+            } else {
+                let gray = ((e[0] as u32 + e[1] as u32 + e[2] as u32) / 12) as u8;
+                [gray, gray, gray, 255]
+            }
+        })
+        .collect();
+
+    (mismatched, diff)
+}
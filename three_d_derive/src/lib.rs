@@ -0,0 +1,202 @@
+//! Derive macros of `three_d`.
+
+use proc_macro::TokenStream;
+use proc_macro2::Literal;
+use quote::quote;
+use std::ffi::CString;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr, Result};
+
+/// Implements `three_d::shader::uniforms::Uniforms` for a struct with named
+/// fields, setting each field as the uniform of the same name in camelCase
+/// (`view_pos` becomes `viewPos`). Fields are attributed with
+/// `#[uniform(name = "...")]` to pick another name, `#[uniform(flatten)]` to
+/// apply a nested `Uniforms` struct or `#[uniform(skip)]` to be left out.
+#[proc_macro_derive(Uniforms, attributes(uniform))]
+pub fn derive_uniforms(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    uniforms(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn uniforms(input: DeriveInput) -> Result<proc_macro2::TokenStream> {
+    let Data::Struct(data) = &input.data else {
+        Err(Error::new_spanned(&input, "Uniforms needs a struct"))?
+    };
+    let Fields::Named(fields) = &data.fields else {
+        Err(Error::new_spanned(&input, "Uniforms needs named fields"))?
+    };
+
+    let mut sets = Vec::new();
+
+    for field in &fields.named {
+        let Some(ident) = &field.ident else {
+            continue;
+        };
+        let mut name = camel_case(&ident.to_string());
+        let mut skip = false;
+        let mut flatten = false;
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("uniform")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else if meta.path.is_ident("flatten") {
+                    flatten = true;
+                    Ok(())
+                } else if meta.path.is_ident("name") {
+                    name = meta.value()?.parse::<LitStr>()?.value();
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `name = \"...\"`, `flatten` or `skip`"))
+                }
+            })?;
+        }
+
+        if skip {
+            continue;
+        }
+
+        if flatten {
+            sets.push(quote! {
+                ::three_d::shader::uniforms::Uniforms::apply(&self.#ident, program);
+            });
+            continue;
+        }
+
+        let name = CString::new(name)
+            .map_err(|_| Error::new_spanned(ident, "uniform names cannot contain NUL"))?;
+        let name = Literal::c_string(&name);
+
+        sets.push(quote! {
+            ::three_d::shader::uniforms::Uniform::set(&self.#ident, program, #name);
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::three_d::shader::uniforms::Uniforms for #ident #ty_generics
+            #where_clause
+        {
+            fn apply(&self, program: &::three_d::shader::shader_program::ShaderProgram) {
+                #(#sets)*
+            }
+        }
+    })
+}
+
+fn camel_case(name: &str) -> String {
+    let mut parts = name.split('_').filter(|part| !part.is_empty());
+    let mut camel = parts.next().unwrap_or_default().to_string();
+
+    for part in parts {
+        let mut chars = part.chars();
+        camel.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        camel.push_str(chars.as_str());
+    }
+
+    camel
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse_quote;
+
+    fn error(input: DeriveInput) -> String {
+        uniforms(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn camel_case_joins_snake_case_words() {
+        assert_eq!(camel_case("time"), "time");
+        assert_eq!(camel_case("view_pos"), "viewPos");
+        assert_eq!(camel_case("light_space_matrix"), "lightSpaceMatrix");
+        assert_eq!(camel_case("cascade_0"), "cascade0");
+        assert_eq!(camel_case("_hidden__twice_"), "hiddenTwice");
+        assert_eq!(camel_case(""), "");
+    }
+
+    #[test]
+    fn fields_set_uniforms_renamed_flattened_or_skipped() {
+        let output = uniforms(parse_quote! {
+            struct Pass<'a> {
+                view_pos: Vec3,
+                #[uniform(name = "u_time")]
+                time: f32,
+                #[uniform(flatten)]
+                camera: CameraUniforms,
+                #[uniform(skip)]
+                scratch: Vec<f32>,
+                diffuse_map: &'a Texture,
+            }
+        })
+        .unwrap();
+
+        let expected = quote! {
+            impl<'a> ::three_d::shader::uniforms::Uniforms for Pass<'a> {
+                fn apply(&self, program: &::three_d::shader::shader_program::ShaderProgram) {
+                    ::three_d::shader::uniforms::Uniform::set(&self.view_pos, program, c"viewPos");
+                    ::three_d::shader::uniforms::Uniform::set(&self.time, program, c"u_time");
+                    ::three_d::shader::uniforms::Uniforms::apply(&self.camera, program);
+                    ::three_d::shader::uniforms::Uniform::set(&self.diffuse_map, program, c"diffuseMap");
+                }
+            }
+        };
+        assert_eq!(output.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn generics_and_where_clauses_carry_over() {
+        let output = uniforms(parse_quote! {
+            struct Wrapper<T> where T: Uniform {
+                value: T,
+            }
+        })
+        .unwrap();
+
+        let expected = quote! {
+            impl<T> ::three_d::shader::uniforms::Uniforms for Wrapper<T>
+                where T: Uniform
+            {
+                fn apply(&self, program: &::three_d::shader::shader_program::ShaderProgram) {
+                    ::three_d::shader::uniforms::Uniform::set(&self.value, program, c"value");
+                }
+            }
+        };
+        assert_eq!(output.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn unsupported_inputs_are_errors() {
+        assert_eq!(
+            error(parse_quote! { enum Pass { A, B } }),
+            "Uniforms needs a struct"
+        );
+        assert_eq!(
+            error(parse_quote! { struct Pass(f32); }),
+            "Uniforms needs named fields"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                struct Pass {
+                    #[uniform(rename = "other")]
+                    time: f32,
+                }
+            }),
+            "expected `name = \"...\"`, `flatten` or `skip`"
+        );
+        assert_eq!(
+            error(parse_quote! {
+                struct Pass {
+                    #[uniform(name = "a\0b")]
+                    time: f32,
+                }
+            }),
+            "uniform names cannot contain NUL"
+        );
+    }
+}